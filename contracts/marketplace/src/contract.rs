@@ -2,23 +2,45 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+    SubMsgResult,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw_utils::parse_reply_instantiate_data;
+use semver::Version;
 
 use crate::error::ContractError;
+use crate::events::{
+    create_collection_event, marketplace_event, optional, CREATE_COLLECTION_FAILED_EVENT,
+};
 use crate::execute::{
-    execute_allow_payment_token, execute_buy, execute_cancel, execute_create_collection,
-    execute_list_nft, execute_mint_nft,
+    collection_version, execute_accept_ownership, execute_add_mint_phase, execute_add_minter,
+    execute_allow_payment_token, execute_batch_mint, execute_buy, execute_cancel,
+    execute_create_collection, execute_grant_role, execute_list_nft, execute_migrate_collections,
+    execute_mint_nft, execute_propose_new_owner, execute_public_mint, execute_register_collection,
+    execute_remove_collection_template, execute_remove_mint_phase, execute_remove_minter,
+    execute_renounce_ownership, execute_revoke_role, execute_set_collection_template,
+    execute_set_paused, execute_sweep_expired, execute_transfer_collection_creator,
+    execute_unregister_collection, execute_update_config, execute_update_listing,
+    execute_update_mint_phase_allowlist, execute_update_protocol_fee, MIGRATE_COLLECTION_REPLY_ID,
 };
+use crate::migrations::run_migrations;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::query::{query_listing, query_listings_by_contract_address};
-use crate::state::{ALLOWED_TOKENS, COLLECTIONS, COLLECTION_ID, CONFIG};
-use crate::structs::Config;
+use crate::query::{
+    query_all_listings, query_collection, query_collection_minters, query_collection_registration,
+    query_collection_stats, query_collection_templates, query_collections,
+    query_collections_by_creator, query_listing, query_listings,
+    query_listings_by_contract_address, query_mint_phases, query_pause_status,
+    query_recent_listings, query_role_holders, query_sales_history, query_wallet_mints,
+};
+use crate::state::{
+    ALLOWED_TOKENS, COLLECTIONS, COLLECTION_ID, COLLECTION_TEMPLATES, CONFIG, PENDING_COLLECTIONS,
+    PENDING_MIGRATIONS,
+};
+use crate::structs::{Config, ListingMode, DEFAULT_COLLECTION_TEMPLATE};
 
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:nft-marketplace";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const CONTRACT_NAME: &str = "crates.io:nft-marketplace";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -28,18 +50,38 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let conf = Config {
-        owner: msg.owner,
-        collection_code_id: msg.collection_code_id,
+        owner: Some(msg.owner),
+        listing_mode: ListingMode::Open,
+        protocol_fee: None,
+        listing_bond: None,
     };
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CONFIG.save(deps.storage, &conf)?;
 
+    // the collections are created from the default template when none is given
+    if msg.collection_templates.is_empty() {
+        return Err(ContractError::NoCollectionTemplates {});
+    }
+    for template in msg.collection_templates {
+        if COLLECTION_TEMPLATES.has(deps.storage, &template.name) {
+            return Err(ContractError::DuplicateCollectionTemplate {
+                name: template.name,
+            });
+        }
+        COLLECTION_TEMPLATES.save(deps.storage, &template.name, &template)?;
+    }
+    if !COLLECTION_TEMPLATES.has(deps.storage, DEFAULT_COLLECTION_TEMPLATE) {
+        return Err(ContractError::CollectionTemplateNotFound {
+            name: DEFAULT_COLLECTION_TEMPLATE.to_string(),
+        });
+    }
+
     COLLECTION_ID.save(deps.storage, &0u64)?;
 
     ALLOWED_TOKENS.save(deps.storage, &vec![])?;
 
     Ok(Response::new()
-        .add_attribute("method", "instantiate")
+        .add_attribute("action", "instantiate")
         .add_attribute("owner", info.sender))
 }
 
@@ -56,51 +98,265 @@ pub fn execute(
             asset,
             listing_config,
         } => execute_list_nft(deps, _env, info, asset, listing_config),
-        ExecuteMsg::Buy { asset } => execute_buy(deps, _env, info, asset),
+        ExecuteMsg::Buy { asset, proof } => execute_buy(deps, _env, info, asset, proof),
+        ExecuteMsg::UpdateListing {
+            asset,
+            price,
+            end_time,
+        } => execute_update_listing(deps, _env, info, asset, price, end_time),
         ExecuteMsg::Cancel { asset } => execute_cancel(deps, _env, info, asset),
-        ExecuteMsg::CreateCollection { name, symbol } => {
-            execute_create_collection(deps, _env, info, name, symbol)
-        }
+        ExecuteMsg::SweepExpired { limit } => execute_sweep_expired(deps, _env, info, limit),
+        ExecuteMsg::CreateCollection {
+            name,
+            symbol,
+            description,
+            image,
+            external_link,
+            max_supply,
+            royalty_bps,
+            royalty_recipient,
+            token_id_mode,
+            template,
+        } => execute_create_collection(
+            deps,
+            _env,
+            info,
+            name,
+            symbol,
+            description,
+            image,
+            external_link,
+            max_supply,
+            royalty_bps,
+            royalty_recipient,
+            token_id_mode.unwrap_or_default(),
+            template.unwrap_or_else(|| DEFAULT_COLLECTION_TEMPLATE.to_string()),
+        ),
         ExecuteMsg::MintNft {
             contract_address,
             token_id,
             token_uri,
-        } => execute_mint_nft(deps, _env, info, contract_address, token_id, token_uri),
+            extension,
+        } => execute_mint_nft(
+            deps,
+            _env,
+            info,
+            contract_address,
+            token_id,
+            token_uri,
+            extension,
+        ),
+        ExecuteMsg::BatchMint {
+            contract_address,
+            tokens,
+        } => execute_batch_mint(deps, _env, info, contract_address, tokens),
         ExecuteMsg::AllowPaymentToken { contract_address } => {
             execute_allow_payment_token(deps, _env, info, contract_address)
         }
+        ExecuteMsg::UpdateConfig {
+            listing_mode,
+            listing_bond,
+        } => execute_update_config(deps, _env, info, listing_mode, listing_bond),
+        ExecuteMsg::SetCollectionTemplate {
+            name,
+            code_id,
+            kind,
+        } => execute_set_collection_template(deps, _env, info, name, code_id, kind),
+        ExecuteMsg::RemoveCollectionTemplate { name } => {
+            execute_remove_collection_template(deps, _env, info, name)
+        }
+        ExecuteMsg::TransferCollectionCreator {
+            contract_address,
+            new_creator,
+        } => execute_transfer_collection_creator(deps, _env, info, contract_address, new_creator),
+        ExecuteMsg::AddMinter {
+            contract_address,
+            minter,
+        } => execute_add_minter(deps, _env, info, contract_address, minter),
+        ExecuteMsg::RemoveMinter {
+            contract_address,
+            minter,
+        } => execute_remove_minter(deps, _env, info, contract_address, minter),
+        ExecuteMsg::MigrateCollections {
+            code_id,
+            collections,
+            msg,
+        } => execute_migrate_collections(deps, _env, info, code_id, collections, msg),
+        ExecuteMsg::AddMintPhase {
+            contract_address,
+            start_time,
+            end_time,
+            price,
+            max_per_wallet,
+            allowlist,
+            base_token_uri,
+            merkle_root,
+        } => execute_add_mint_phase(
+            deps,
+            _env,
+            info,
+            contract_address,
+            start_time,
+            end_time,
+            price,
+            max_per_wallet,
+            allowlist,
+            base_token_uri,
+            merkle_root,
+        ),
+        ExecuteMsg::UpdateMintPhaseAllowlist {
+            contract_address,
+            phase_id,
+            add,
+            remove,
+        } => execute_update_mint_phase_allowlist(
+            deps,
+            _env,
+            info,
+            contract_address,
+            phase_id,
+            add,
+            remove,
+        ),
+        ExecuteMsg::RemoveMintPhase {
+            contract_address,
+            phase_id,
+        } => execute_remove_mint_phase(deps, _env, info, contract_address, phase_id),
+        ExecuteMsg::PublicMint {
+            contract_address,
+            phase_id,
+            proof,
+        } => execute_public_mint(deps, _env, info, contract_address, phase_id, proof),
+        ExecuteMsg::UpdateProtocolFee {
+            fee_bps,
+            fee_recipient,
+        } => execute_update_protocol_fee(deps, _env, info, fee_bps, fee_recipient),
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, _env, info, new_owner)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, _env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, _env, info),
+        ExecuteMsg::RegisterCollection {
+            contract_address,
+            verified,
+            display_name,
+            payment_denoms,
+        } => execute_register_collection(
+            deps,
+            _env,
+            info,
+            contract_address,
+            verified,
+            display_name,
+            payment_denoms,
+        ),
+        ExecuteMsg::UnregisterCollection { contract_address } => {
+            execute_unregister_collection(deps, _env, info, contract_address)
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            execute_grant_role(deps, _env, info, role, address)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            execute_revoke_role(deps, _env, info, role, address)
+        }
+        ExecuteMsg::Pause { operations } => execute_set_paused(deps, _env, info, operations, true),
+        ExecuteMsg::Unpause { operations } => {
+            execute_set_paused(deps, _env, info, operations, false)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+
+    // only allow migrating from this contract
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            stored: stored.contract,
+            expected: CONTRACT_NAME.to_string(),
+        });
+    }
+
+    // never allow to go back to an older version
+    let stored_version = Version::parse(&stored.version)?;
+    let new_version = Version::parse(CONTRACT_VERSION)?;
+    if stored_version > new_version {
+        return Err(ContractError::CannotDowngrade {
+            stored: stored.version,
+            new: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    // upgrade the storage step by step, then record the new version
+    let applied = run_migrations(deps.branch(), &env, &stored_version, &new_version)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("applied_steps", applied.join(",")))
 }
 
 /// This just stores the result for future query
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let reply = parse_reply_instantiate_data(msg.clone()).unwrap();
+    if msg.id >= MIGRATE_COLLECTION_REPLY_ID {
+        return migrate_collection_reply(deps, msg);
+    }
 
-    let collection_contract = &reply.contract_address;
+    // load the pending collection based on the msg.id
+    let pending = PENDING_COLLECTIONS
+        .may_load(deps.storage, msg.id)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    PENDING_COLLECTIONS.remove(deps.storage, msg.id);
 
-    // load minter from collections based on the msg.id
-    let minter = COLLECTIONS.load(deps.storage, msg.id.to_string())?;
+    // the instantiation failed, the pending collection is dropped so the creator can try again
+    if let SubMsgResult::Err(error) = &msg.result {
+        return Ok(Response::new().add_event(
+            marketplace_event(CREATE_COLLECTION_FAILED_EVENT)
+                .add_attribute("reply_id", msg.id.to_string())
+                .add_attribute("creator", pending.collection.creator.as_str())
+                .add_attribute("error", error),
+        ));
+    }
+
+    let reply = parse_reply_instantiate_data(msg)?;
+    let collection_contract = deps.api.addr_validate(&reply.contract_address)?;
+
+    // save the created collection
+    let mut collection = pending.collection;
+    collection.code_version = collection_version(&deps.querier, &collection_contract);
+    COLLECTIONS.save(deps.storage, &collection_contract, &collection)?;
+    Ok(Response::new()
+        .add_event(create_collection_event(&collection_contract, &collection))
+        .add_attributes(vec![
+            ("action", "create_collection_reply"),
+            ("collection_contract", collection_contract.as_str()),
+            ("minter", collection.creator.as_str()),
+        ]))
+}
+
+// record the version of a migrated collection
+fn migrate_collection_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let collection_address = PENDING_MIGRATIONS
+        .may_load(deps.storage, msg.id)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    PENDING_MIGRATIONS.remove(deps.storage, msg.id);
 
-    // remove msg.id from collections
-    COLLECTIONS.remove(deps.storage, msg.id.to_string());
+    let mut collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+    collection.code_version = collection_version(&deps.querier, &collection_address);
+    COLLECTIONS.save(deps.storage, &collection_address, &collection)?;
 
-    // save minter to collections
-    COLLECTIONS.save(deps.storage, collection_contract.to_string(), &minter)?;
-    Ok(Response::new().add_attributes(vec![
-        ("action", "create_collection_reply"),
-        ("collection_contract", collection_contract),
-        ("minter", &minter),
-    ]))
+    Ok(Response::new()
+        .add_attribute("action", "migrate_collection_reply")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("code_version", optional(collection.code_version)))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     let _api = deps.api;
     match msg {
         // get config
@@ -119,5 +375,85 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             contract_address,
             token_id,
         } => to_json_binary(&query_listing(deps, contract_address, token_id)?),
+        QueryMsg::Collection { contract_address } => {
+            to_json_binary(&query_collection(deps, contract_address)?)
+        }
+        QueryMsg::Collections { start_after, limit } => {
+            to_json_binary(&query_collections(deps, start_after, limit)?)
+        }
+        QueryMsg::CollectionsByCreator {
+            creator,
+            start_after,
+            limit,
+        } => to_json_binary(&query_collections_by_creator(
+            deps,
+            creator,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::CollectionRegistration { contract_address } => {
+            to_json_binary(&query_collection_registration(deps, contract_address)?)
+        }
+        QueryMsg::Listings {
+            contract_address,
+            filter,
+            sort,
+            start_after,
+            limit,
+        } => to_json_binary(&query_listings(
+            deps,
+            env,
+            contract_address,
+            filter,
+            sort,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllListings {
+            start_after,
+            limit,
+            order,
+        } => to_json_binary(&query_all_listings(deps, start_after, limit, order)?),
+        QueryMsg::RecentListings { start_after, limit } => {
+            to_json_binary(&query_recent_listings(deps, start_after, limit)?)
+        }
+        QueryMsg::CollectionStats { contract_address } => {
+            to_json_binary(&query_collection_stats(deps, env, contract_address)?)
+        }
+        QueryMsg::SalesHistory {
+            filter,
+            start_after,
+            limit,
+        } => to_json_binary(&query_sales_history(deps, filter, start_after, limit)?),
+        QueryMsg::CollectionTemplates {} => to_json_binary(&query_collection_templates(deps)?),
+        QueryMsg::CollectionMinters {
+            contract_address,
+            start_after,
+            limit,
+        } => to_json_binary(&query_collection_minters(
+            deps,
+            contract_address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::MintPhases { contract_address } => {
+            to_json_binary(&query_mint_phases(deps, contract_address)?)
+        }
+        QueryMsg::WalletMints {
+            contract_address,
+            phase_id,
+            address,
+        } => to_json_binary(&query_wallet_mints(
+            deps,
+            contract_address,
+            phase_id,
+            address,
+        )?),
+        QueryMsg::PauseStatus {} => to_json_binary(&query_pause_status(deps)?),
+        QueryMsg::RoleHolders {
+            role,
+            start_after,
+            limit,
+        } => to_json_binary(&query_role_holders(deps, role, start_after, limit)?),
     }
 }
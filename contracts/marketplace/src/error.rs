@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError, Timestamp};
+use cw_utils::{Expiration, ParseReplyError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,15 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Semver parsing error: {0}")]
+    SemVer(#[from] semver::Error),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -15,13 +25,60 @@ pub enum ContractError {
     #[error("Listing Not Active")]
     ListingNotActive {},
 
+    #[error("Listing has not started, it starts at {start_time}")]
+    ListingNotStarted { start_time: Expiration },
+
+    #[error("Listing has expired at {end_time}")]
+    ListingExpired { end_time: Expiration },
+
+    #[error("Invalid listing config")]
+    InvalidListingConfig {},
+
+    #[error("Only fixed price listings are supported")]
+    UnsupportedAuctionConfig {},
+
+    #[error("Token ID is required")]
+    TokenIdRequired {},
+
+    #[error("Token ID is assigned by the marketplace")]
+    TokenIdNotAllowed {},
+
+    #[error("A never expiring approval of the marketplace is required")]
+    ApprovalRequired {},
+
+    #[error("The approval of the marketplace expires at {expires}, a never expiring approval is required")]
+    ApprovalExpiring { expires: Expiration },
+
+    #[error("Owner cannot buy their own listing")]
+    CannotBuyOwnListing {},
+
+    #[error("Payment token {contract_address} is not allowed")]
+    PaymentTokenNotAllowed { contract_address: String },
+
+    #[error("Payment must be a native coin or a cw20 token")]
+    InvalidPaymentAsset {},
+
+    #[error("No funds are required")]
+    UnexpectedFunds {},
+
     #[error("Insufficient Funds")]
     InsufficientFunds {},
 
-    #[error("Custom Error val: {val:?}")]
-    CustomError { val: String },
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+    #[error("End time {end_time} has already passed")]
+    EndTimeExpired { end_time: Expiration },
+
+    #[error("End time {end_time} must be after the start time {start_time}")]
+    EndTimeBeforeStartTime {
+        start_time: Expiration,
+        end_time: Expiration,
+    },
+
+    #[error("Arithmetic overflow")]
+    Overflow {},
+
+    #[error("A listing bond of {bond} is required")]
+    ListingBondRequired { bond: Coin },
+
     #[error("Offer item must be a nft")]
     OfferEmpty {},
 
@@ -45,4 +102,94 @@ pub enum ContractError {
 
     #[error("VAura address not set")]
     VauraAddressNotSet {},
+
+    #[error("Operation {operation} is paused")]
+    OperationPaused { operation: String },
+
+    #[error("Collection {contract_address} is not verified")]
+    CollectionNotVerified { contract_address: String },
+
+    #[error("Payment denom {denom} is not allowed for collection {contract_address}")]
+    PaymentDenomNotAllowed {
+        contract_address: String,
+        denom: String,
+    },
+
+    #[error("Royalty of {bps} bps exceeds the maximum of {max} bps")]
+    RoyaltyTooHigh { bps: u16, max: u16 },
+
+    #[error("Royalty recipient requires royalty bps")]
+    RoyaltyRecipientWithoutBps {},
+
+    #[error("Max supply must be greater than zero")]
+    InvalidMaxSupply {},
+
+    #[error("Max supply of {max_supply} tokens reached")]
+    MaxSupplyReached { max_supply: u64 },
+
+    #[error("Collection template {name} not found")]
+    CollectionTemplateNotFound { name: String },
+
+    #[error("At least one collection template is required")]
+    NoCollectionTemplates {},
+
+    #[error("Collection template {name} is duplicated")]
+    DuplicateCollectionTemplate { name: String },
+
+    #[error("Soulbound collection {contract_address} cannot be listed")]
+    SoulboundNotListable { contract_address: String },
+
+    #[error("Collection {contract_address} cannot be migrated to or from a soulbound kind")]
+    SoulboundKindChange { contract_address: String },
+
+    #[error("Collection {contract_address} does not store metadata on-chain")]
+    OnchainMetadataNotSupported { contract_address: String },
+
+    #[error("Batch must contain between 1 and {max_size} tokens")]
+    InvalidBatchSize { max_size: u32 },
+
+    #[error("Protocol fee of {bps} bps exceeds the maximum of {max} bps")]
+    ProtocolFeeTooHigh { bps: u16, max: u16 },
+
+    #[error("Address {minter} is already a minter")]
+    MinterAlreadyExists { minter: String },
+
+    #[error("Collection {contract_address} must use sequential token ids to have mint phases")]
+    MintPhaseRequiresSequentialIds { contract_address: String },
+
+    #[error("Mint phase end time {end_time} must be after the start time {start_time} and in the future")]
+    InvalidMintPhaseTime {
+        start_time: Timestamp,
+        end_time: Timestamp,
+    },
+
+    #[error("Max per wallet must be greater than zero")]
+    InvalidMaxPerWallet {},
+
+    #[error("Mint phase {phase_id} not found")]
+    MintPhaseNotFound { phase_id: u32 },
+
+    #[error("Mint phase {phase_id} is not active")]
+    MintPhaseNotActive { phase_id: u32 },
+
+    #[error("Address is not in the allowlist of mint phase {phase_id}")]
+    NotInAllowlist { phase_id: u32 },
+
+    #[error("Invalid merkle root")]
+    InvalidMerkleRoot {},
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("Wallet limit of {max_per_wallet} tokens reached")]
+    WalletLimitReached { max_per_wallet: u32 },
+
+    #[error("No pending owner")]
+    NoPendingOwner {},
+
+    #[error("Cannot migrate from contract {stored} to {expected}")]
+    InvalidContractName { stored: String, expected: String },
+
+    #[error("Cannot migrate from version {stored} to older version {new}")]
+    CannotDowngrade { stored: String, new: String },
 }
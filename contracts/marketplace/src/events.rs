@@ -0,0 +1,146 @@
+use cosmwasm_std::{Addr, Event};
+use cw721::Expiration;
+
+use crate::state::{EXPIRY_AT_HEIGHT, EXPIRY_AT_TIME, EXPIRY_NEVER};
+use crate::structs::{CollectionInfo, Order, PaymentAsset};
+
+// the version of the event schema documented in schema/events.md
+// !bump it on every change of an event type or an attribute name
+pub const EVENT_SCHEMA_VERSION: &str = "1";
+
+// the chain prefixes the custom events with "wasm-", e.g. "wasm-marketplace-list"
+pub const LIST_EVENT: &str = "marketplace-list";
+pub const REPLACE_LISTING_EVENT: &str = "marketplace-replace-listing";
+pub const UPDATE_LISTING_EVENT: &str = "marketplace-update-listing";
+pub const CANCEL_EVENT: &str = "marketplace-cancel";
+pub const EXPIRE_EVENT: &str = "marketplace-expire";
+pub const SALE_EVENT: &str = "marketplace-sale";
+pub const MINT_EVENT: &str = "marketplace-mint";
+
+// the events of the collections created by the marketplace
+pub const CREATE_COLLECTION_EVENT: &str = "marketplace-create-collection";
+pub const CREATE_COLLECTION_FAILED_EVENT: &str = "marketplace-create-collection-failed";
+pub const CREATOR_MINT_EVENT: &str = "marketplace-creator-mint";
+pub const TRANSFER_COLLECTION_CREATOR_EVENT: &str = "marketplace-transfer-collection-creator";
+pub const ADD_MINTER_EVENT: &str = "marketplace-add-minter";
+pub const REMOVE_MINTER_EVENT: &str = "marketplace-remove-minter";
+pub const MIGRATE_COLLECTION_EVENT: &str = "marketplace-migrate-collection";
+pub const ADD_MINT_PHASE_EVENT: &str = "marketplace-add-mint-phase";
+pub const UPDATE_MINT_PHASE_ALLOWLIST_EVENT: &str = "marketplace-update-mint-phase-allowlist";
+pub const REMOVE_MINT_PHASE_EVENT: &str = "marketplace-remove-mint-phase";
+
+// the events of the administration of the marketplace
+pub const UPDATE_CONFIG_EVENT: &str = "marketplace-update-config";
+pub const UPDATE_PROTOCOL_FEE_EVENT: &str = "marketplace-update-protocol-fee";
+pub const ALLOW_PAYMENT_TOKEN_EVENT: &str = "marketplace-allow-payment-token";
+pub const SET_COLLECTION_TEMPLATE_EVENT: &str = "marketplace-set-collection-template";
+pub const REMOVE_COLLECTION_TEMPLATE_EVENT: &str = "marketplace-remove-collection-template";
+pub const REGISTER_COLLECTION_EVENT: &str = "marketplace-register-collection";
+pub const UNREGISTER_COLLECTION_EVENT: &str = "marketplace-unregister-collection";
+pub const PROPOSE_OWNER_EVENT: &str = "marketplace-propose-owner";
+pub const ACCEPT_OWNERSHIP_EVENT: &str = "marketplace-accept-ownership";
+pub const RENOUNCE_OWNERSHIP_EVENT: &str = "marketplace-renounce-ownership";
+pub const GRANT_ROLE_EVENT: &str = "marketplace-grant-role";
+pub const REVOKE_ROLE_EVENT: &str = "marketplace-revoke-role";
+pub const PAUSE_EVENT: &str = "marketplace-pause";
+pub const UNPAUSE_EVENT: &str = "marketplace-unpause";
+
+// attribute values cannot be empty, unset values are written as "none"
+pub const NONE: &str = "none";
+
+pub fn marketplace_event(ty: &str) -> Event {
+    Event::new(ty).add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+}
+
+// a transition made by the sender, e.g. an admin or the creator of a collection
+pub fn sender_event(ty: &str, sender: &Addr) -> Event {
+    marketplace_event(ty).add_attribute("sender", sender)
+}
+
+// an optional value, written as "none" when unset
+pub fn optional(value: Option<impl ToString>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| NONE.to_string())
+}
+
+// an expiration is written as its kind, and the height or the time in nanoseconds,
+// e.g. end_time_kind = "height" and end_time = "100"
+fn add_expiration(event: Event, name: &str, expiration: &Option<Expiration>) -> Event {
+    let (kind, value) = match expiration {
+        Some(Expiration::AtHeight(height)) => (EXPIRY_AT_HEIGHT, height.to_string()),
+        Some(Expiration::AtTime(time)) => (EXPIRY_AT_TIME, time.nanos().to_string()),
+        Some(Expiration::Never {}) => (EXPIRY_NEVER, NONE.to_string()),
+        None => (NONE, NONE.to_string()),
+    };
+    event
+        .add_attribute(format!("{}_kind", name), kind)
+        .add_attribute(name, value)
+}
+
+// the attributes shared by every event of a listing
+pub fn listing_event(ty: &str, listing: &Order) -> Event {
+    let (contract_address, token_id) = (&listing.order_id.1, &listing.order_id.2);
+    let (denom, price) = listing.price_key();
+    let event = marketplace_event(ty)
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", &listing.owner)
+        .add_attribute("price", price.to_string())
+        .add_attribute(
+            "denom",
+            optional(Some(denom).filter(|denom| !denom.is_empty())),
+        );
+    let event = add_expiration(event, "start_time", &listing.start_time);
+    add_expiration(event, "end_time", &listing.end_time)
+        .add_attribute("version", listing.version.to_string())
+}
+
+// a listing bought by the buyer, the seller receives the price minus the royalty
+pub fn sale_event(listing: &Order, sale_id: u64, buyer: &Addr, royalty: u128) -> Event {
+    listing_event(SALE_EVENT, listing)
+        .add_attribute("sale_id", sale_id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("royalty", royalty.to_string())
+}
+
+// a token bought from a mint phase, the creator receives the price minus the fee
+pub fn mint_event(
+    contract_address: &Addr,
+    token_id: &str,
+    phase_id: u32,
+    buyer: &Addr,
+    price: &PaymentAsset,
+    fee: u128,
+) -> Event {
+    marketplace_event(MINT_EVENT)
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("token_id", token_id)
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price.amount().to_string())
+        .add_attribute("denom", price.denom())
+        .add_attribute("fee", fee.to_string())
+}
+
+// a collection instantiated by the marketplace
+pub fn create_collection_event(contract_address: &Addr, collection: &CollectionInfo) -> Event {
+    let royalty = collection.royalty.as_ref();
+    marketplace_event(CREATE_COLLECTION_EVENT)
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("creator", &collection.creator)
+        .add_attribute("name", &collection.name)
+        .add_attribute("symbol", &collection.symbol)
+        .add_attribute("kind", collection.kind.as_str())
+        .add_attribute("code_id", optional(collection.code_id))
+        .add_attribute("token_id_mode", collection.token_id_mode.as_str())
+        .add_attribute("max_supply", optional(collection.max_supply))
+        .add_attribute(
+            "royalty_bps",
+            royalty.map(|royalty| royalty.bps).unwrap_or(0).to_string(),
+        )
+        .add_attribute(
+            "royalty_recipient",
+            optional(royalty.map(|royalty| &royalty.recipient)),
+        )
+}
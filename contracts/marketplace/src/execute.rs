@@ -1,18 +1,50 @@
 use crate::{
     error::ContractError,
-    state::{listing_key, ALLOWED_TOKENS, COLLECTIONS, COLLECTION_ID, CONFIG, LISTINGS},
+    events::{
+        listing_event, mint_event, optional, sale_event, sender_event, ACCEPT_OWNERSHIP_EVENT,
+        ADD_MINTER_EVENT, ADD_MINT_PHASE_EVENT, ALLOW_PAYMENT_TOKEN_EVENT, CANCEL_EVENT,
+        CREATOR_MINT_EVENT, EXPIRE_EVENT, GRANT_ROLE_EVENT, LIST_EVENT, MIGRATE_COLLECTION_EVENT,
+        PAUSE_EVENT, PROPOSE_OWNER_EVENT, REGISTER_COLLECTION_EVENT,
+        REMOVE_COLLECTION_TEMPLATE_EVENT, REMOVE_MINTER_EVENT, REMOVE_MINT_PHASE_EVENT,
+        RENOUNCE_OWNERSHIP_EVENT, REPLACE_LISTING_EVENT, REVOKE_ROLE_EVENT,
+        SET_COLLECTION_TEMPLATE_EVENT, TRANSFER_COLLECTION_CREATOR_EVENT, UNPAUSE_EVENT,
+        UNREGISTER_COLLECTION_EVENT, UPDATE_CONFIG_EVENT, UPDATE_LISTING_EVENT,
+        UPDATE_MINT_PHASE_ALLOWLIST_EVENT, UPDATE_PROTOCOL_FEE_EVENT,
+    },
+    state::{
+        listing_key, ListingKey, ALLOWED_TOKENS, COLLECTIONS, COLLECTION_ID, COLLECTION_MINTERS,
+        COLLECTION_REGISTRY, COLLECTION_STATS, COLLECTION_TEMPLATES, CONFIG, EXPIRY_AT_HEIGHT,
+        EXPIRY_AT_TIME, LISTINGS, MINTED_COUNT, MINT_PHASES, MINT_PHASE_ALLOWLIST,
+        MINT_PHASE_WALLET_MINTS, NEXT_MINT_PHASE_ID, PAUSE_STATUS, PENDING_COLLECTIONS,
+        PENDING_MIGRATIONS, PENDING_OWNER, ROLES, SALES, SALE_COUNT,
+    },
     structs::{
-        order_id, Asset, AuctionConfig, ConsiderationItem, Cw20Asset, ItemType, NativeAsset,
-        NftAsset, OfferItem, Order, OrderType, PaymentAsset,
+        is_valid_merkle_root, order_id, Asset, AuctionConfig, CollectionInfo, CollectionKind,
+        CollectionRegistration, CollectionStats, CollectionTemplate, ConsiderationItem, Cw20Asset,
+        ItemType, ListingMode, MerkleProof, Metadata, MintItem, MintPhase, NativeAsset, NftAsset,
+        OfferItem, Order, OrderType, PausableOperation, PaymentAsset, PendingCollection,
+        ProtocolFee, Role, Royalty, Sale, TokenIdMode, MAX_PROTOCOL_FEE_BPS, MAX_ROYALTY_BPS,
     },
 };
 use cosmwasm_std::{
-    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Empty, Env, MessageInfo, QueryRequest,
-    ReplyOn, Response, StdResult, SubMsg, Uint128, WasmMsg, WasmQuery,
+    to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, DepsMut, Empty, Env, MessageInfo,
+    Order as StdOrder, QuerierWrapper, QueryRequest, ReplyOn, Response, StdResult, Storage, SubMsg,
+    Timestamp, Uint128, WasmMsg, WasmQuery,
 };
 use cw20::Cw20ExecuteMsg;
 use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, Expiration as Cw721Expiration};
-use cw721_base::{Extension, InstantiateMsg as Cw721InstantiateMsg};
+use cw721_base::InstantiateMsg as Cw721InstantiateMsg;
+
+// the maximum number of tokens minted by a single batch
+pub const MAX_BATCH_MINT_SIZE: u32 = 50;
+// the maximum number of collections migrated by a single message
+pub const MAX_MIGRATE_COLLECTIONS_SIZE: u32 = 20;
+// the reply ids of the migrations, the n-th collection of a message uses this id + n,
+// the reply ids below it are the ids of the created collections
+pub const MIGRATE_COLLECTION_REPLY_ID: u64 = 1 << 63;
+// the default and maximum number of expired listings removed by a single sweep
+pub const DEFAULT_SWEEP_EXPIRED_LIMIT: u32 = 30;
+pub const MAX_SWEEP_EXPIRED_LIMIT: u32 = 100;
 
 pub fn execute_list_nft(
     deps: DepsMut,
@@ -21,21 +53,57 @@ pub fn execute_list_nft(
     asset: NftAsset,
     auction_config: AuctionConfig,
 ) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Listing)?;
+
     // auction time must be valid first
     if !auction_config.is_valid() {
-        return Err(ContractError::CustomError {
-            val: "Invalid listing config".to_string(),
-        });
+        return Err(ContractError::InvalidListingConfig {});
     }
 
     let contract_address = asset.contract_address.clone();
     // token_id is required
-    if asset.token_id.is_none() {
-        return Err(ContractError::CustomError {
-            val: "Token ID is required".to_string(),
+    let token_id = asset.token_id.ok_or(ContractError::TokenIdRequired {})?;
+
+    // the tokens of soulbound collections cannot be transferred to a buyer
+    let created_collection = COLLECTIONS.may_load(deps.storage, &contract_address)?;
+    if created_collection
+        .as_ref()
+        .is_some_and(|collection| collection.kind == CollectionKind::Soulbound)
+    {
+        return Err(ContractError::SoulboundNotListable {
+            contract_address: contract_address.to_string(),
         });
     }
-    let token_id = asset.token_id.unwrap();
+
+    // check if the collection can be listed
+    let config = CONFIG.load(deps.storage)?;
+    let registration = COLLECTION_REGISTRY.may_load(deps.storage, &contract_address)?;
+    if config.listing_mode == ListingMode::VerifiedOnly {
+        let verified = registration
+            .as_ref()
+            .map(|registration| registration.verified)
+            .unwrap_or(false);
+        if !verified && created_collection.is_none() {
+            return Err(ContractError::CollectionNotVerified {
+                contract_address: contract_address.to_string(),
+            });
+        }
+    }
+
+    // the listing bond is held until the listing is sold, cancelled or swept
+    if let Some(bond) = &config.listing_bond {
+        if info.funds != [bond.clone()] {
+            return Err(ContractError::ListingBondRequired { bond: bond.clone() });
+        }
+    }
+
+    // only fixed price listings are supported,
+    // and their price must be paid with a denom accepted by the collection
+    let price = match &auction_config {
+        AuctionConfig::FixedPrice { price, .. } => price,
+        _ => return Err(ContractError::UnsupportedAuctionConfig {}),
+    };
+    ensure_payment_denom_allowed(registration.as_ref(), &contract_address, price.denom())?;
 
     // check if user is the owner of the token
     let owner_response: StdResult<cw721::OwnerOfResponse> =
@@ -71,21 +139,20 @@ pub fn execute_list_nft(
     match approval_response {
         Ok(approval) => match approval.approval.expires {
             Cw721Expiration::Never {} => {}
-            _ => return Err(ContractError::Unauthorized {}),
+            expires => return Err(ContractError::ApprovalExpiring { expires }),
         },
-        Err(_) => {
-            return Err(ContractError::CustomError {
-                val: "Require never expired approval".to_string(),
-            });
-        }
+        Err(_) => return Err(ContractError::ApprovalRequired {}),
     }
 
     // the auction_config must be FixedPrice
-    match auction_config.clone() {
+    let mut previous_bond_refund = None;
+    let mut replace_event = None;
+    let list_event = match auction_config.clone() {
         AuctionConfig::FixedPrice {
             price,
             start_time,
             end_time,
+            merkle_root,
         } => {
             // add new listing to orders
             let order_id = order_id(&info.sender, &contract_address, &token_id);
@@ -100,37 +167,7 @@ pub fn execute_list_nft(
                 end_amount: 1,
             };
 
-            let consideration_item = match price {
-                PaymentAsset::Native { denom, amount } => ConsiderationItem {
-                    item_type: ItemType::NATIVE,
-                    item: Asset::Native(NativeAsset { denom, amount }),
-                    start_amount: amount,
-                    end_amount: amount,
-                    recipient: info.sender.clone(),
-                },
-                PaymentAsset::Cw20 {
-                    contract_address,
-                    amount,
-                } => {
-                    // check if contract_address is in ALLOWED_TOKENS
-                    let allowed_tokens: Vec<Addr> = ALLOWED_TOKENS.load(deps.storage)?;
-                    if !allowed_tokens.contains(&contract_address) {
-                        return Err(ContractError::CustomError {
-                            val: "Payment token not allowed".to_string(),
-                        });
-                    }
-                    ConsiderationItem {
-                        item_type: ItemType::CW20,
-                        item: Asset::Cw20(Cw20Asset {
-                            contract_address,
-                            amount,
-                        }),
-                        start_amount: amount,
-                        end_amount: amount,
-                        recipient: info.sender.clone(),
-                    }
-                }
-            };
+            let consideration_item = consideration_item(deps.storage, price, &info.sender)?;
 
             let new_listing = Order {
                 order_type: OrderType::LISTING,
@@ -140,36 +177,171 @@ pub fn execute_list_nft(
                 consideration: vec![consideration_item],
                 start_time,
                 end_time,
+                merkle_root,
+                height: env.block.height,
+                bond: config.listing_bond.clone(),
+                version: 0,
             };
 
             let listing_key = listing_key(&contract_address, &token_id);
             // we will override the order if it already exists, so that we can update the auction config
-            LISTINGS.update(
-                deps.storage,
-                listing_key,
-                |_old| -> Result<Order, ContractError> { Ok(new_listing) },
-            )?;
+            let previous_listing = LISTINGS.may_load(deps.storage, listing_key.clone())?;
+            LISTINGS.save(deps.storage, listing_key, &new_listing)?;
+
+            // the overridden listing is no longer counted in the stats, its bond is refunded
+            if let Some(previous_listing) = previous_listing {
+                update_listing_count(deps.storage, &previous_listing, false)?;
+                previous_bond_refund =
+                    release_listing_bond(&previous_listing, &previous_listing.owner);
+                replace_event = Some(
+                    listing_event(REPLACE_LISTING_EVENT, &previous_listing)
+                        .add_attribute("replaced_by", &info.sender),
+                );
+            }
+            update_listing_count(deps.storage, &new_listing, true)?;
+            listing_event(LIST_EVENT, &new_listing)
+        }
+        _ => return Err(ContractError::UnsupportedAuctionConfig {}),
+    };
+
+    Ok(Response::new()
+        .add_messages(previous_bond_refund)
+        .add_events(replace_event)
+        .add_event(list_event)
+        .add_attribute("action", "list_nft")
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", info.sender.to_string()))
+}
+
+// the price payable to the seller of a listing
+fn consideration_item(
+    storage: &dyn Storage,
+    price: PaymentAsset,
+    recipient: &Addr,
+) -> Result<ConsiderationItem, ContractError> {
+    match price {
+        PaymentAsset::Native { denom, amount } => Ok(ConsiderationItem {
+            item_type: ItemType::NATIVE,
+            item: Asset::Native(NativeAsset { denom, amount }),
+            start_amount: amount,
+            end_amount: amount,
+            recipient: recipient.clone(),
+        }),
+        PaymentAsset::Cw20 {
+            contract_address,
+            amount,
+        } => {
+            // check if contract_address is in ALLOWED_TOKENS
+            let allowed_tokens: Vec<Addr> = ALLOWED_TOKENS.load(storage)?;
+            if !allowed_tokens.contains(&contract_address) {
+                return Err(ContractError::PaymentTokenNotAllowed {
+                    contract_address: contract_address.to_string(),
+                });
+            }
+            Ok(ConsiderationItem {
+                item_type: ItemType::CW20,
+                item: Asset::Cw20(Cw20Asset {
+                    contract_address,
+                    amount,
+                }),
+                start_amount: amount,
+                end_amount: amount,
+                recipient: recipient.clone(),
+            })
         }
-        _ => {
-            return Err(ContractError::CustomError {
-                val: "Auction Config Error".to_string(),
+    }
+}
+
+// return an error if the registration of the collection restricts the payment denoms
+fn ensure_payment_denom_allowed(
+    registration: Option<&CollectionRegistration>,
+    contract_address: &Addr,
+    denom: String,
+) -> Result<(), ContractError> {
+    if let Some(payment_denoms) =
+        registration.and_then(|registration| registration.payment_denoms.as_ref())
+    {
+        if !payment_denoms.contains(&denom) {
+            return Err(ContractError::PaymentDenomNotAllowed {
+                contract_address: contract_address.to_string(),
+                denom,
             });
         }
     }
+    Ok(())
+}
 
-    let auction_config_str = serde_json::to_string(&auction_config);
+// the seller changes the price or the end_time of a listing, without listing it again
+pub fn execute_update_listing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: NftAsset,
+    price: Option<PaymentAsset>,
+    end_time: Option<Cw721Expiration>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Listing)?;
 
-    match auction_config_str {
-        Ok(auction_config_str) => Ok(Response::new()
-            .add_attribute("method", "list_nft")
-            .add_attribute("contract_address", contract_address)
-            .add_attribute("token_id", token_id)
-            .add_attribute("auction_config", auction_config_str)
-            .add_attribute("seller", info.sender.to_string())),
-        Err(_) => Err(ContractError::CustomError {
-            val: ("Auction Config Error".to_string()),
-        }),
+    let contract_address = asset.contract_address.clone();
+    // token_id is required
+    let token_id = asset.token_id.ok_or(ContractError::TokenIdRequired {})?;
+
+    let listing_key = listing_key(&contract_address, &token_id);
+    let listing = LISTINGS.load(deps.storage, listing_key.clone())?;
+    if listing.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    // an expired listing must be listed again, so that the ownership is checked
+    if listing.is_expired(&env.block) {
+        return Err(ContractError::ListingExpired {
+            end_time: listing.end_time.unwrap_or_default(),
+        });
+    }
+
+    let mut updated_listing = listing.clone();
+    if let Some(price) = price {
+        let registration = COLLECTION_REGISTRY.may_load(deps.storage, &contract_address)?;
+        ensure_payment_denom_allowed(registration.as_ref(), &contract_address, price.denom())?;
+        updated_listing.consideration =
+            vec![consideration_item(deps.storage, price, &info.sender)?];
     }
+    if let Some(end_time) = end_time {
+        if end_time.is_expired(&env.block) {
+            return Err(ContractError::EndTimeExpired { end_time });
+        }
+        if let Some(start_time) = updated_listing
+            .start_time
+            .filter(|start_time| *start_time >= end_time)
+        {
+            return Err(ContractError::EndTimeBeforeStartTime {
+                start_time,
+                end_time,
+            });
+        }
+        updated_listing.end_time = Some(end_time);
+    }
+    updated_listing.version += 1;
+
+    // the listing is counted under its new denom
+    LISTINGS.save(deps.storage, listing_key, &updated_listing)?;
+    update_listing_count(deps.storage, &listing, false)?;
+    update_listing_count(deps.storage, &updated_listing, true)?;
+
+    let (denom, amount) = updated_listing.price_key();
+    let end_time = updated_listing
+        .end_time
+        .map(|end_time| end_time.to_string())
+        .unwrap_or_else(|| "none".to_string());
+    Ok(Response::new()
+        .add_event(listing_event(UPDATE_LISTING_EVENT, &updated_listing))
+        .add_attribute("action", "update_listing")
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("token_id", token_id)
+        .add_attribute("version", updated_listing.version.to_string())
+        .add_attribute("price", amount.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("end_time", end_time))
 }
 
 pub fn execute_buy(
@@ -177,15 +349,13 @@ pub fn execute_buy(
     env: Env,
     info: MessageInfo,
     asset: NftAsset,
+    proof: Option<MerkleProof>,
 ) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Buying)?;
+
     let contract_address = asset.contract_address.clone();
     // token_id is required
-    if asset.token_id.is_none() {
-        return Err(ContractError::CustomError {
-            val: "Token ID is required".to_string(),
-        });
-    }
-    let token_id = asset.token_id.unwrap();
+    let token_id = asset.token_id.ok_or(ContractError::TokenIdRequired {})?;
 
     // get the listing
     let listing_key = listing_key(&contract_address, &token_id);
@@ -193,29 +363,31 @@ pub fn execute_buy(
 
     // check if owner of listing is the same as seller
     if info.sender == listing.owner {
-        return Err(ContractError::CustomError {
-            val: ("Owner cannot buy".to_string()),
-        });
+        return Err(ContractError::CannotBuyOwnListing {});
+    }
+
+    // a private listing can only be bought by the addresses of its merkle tree
+    if let Some(merkle_root) = &listing.merkle_root {
+        if !proof.is_some_and(|proof| proof.verify(merkle_root, &info.sender)) {
+            return Err(ContractError::InvalidMerkleProof {});
+        }
     }
 
     // remove the listing
     LISTINGS.remove(deps.storage, listing_key)?;
+    update_listing_count(deps.storage, &listing, false)?;
 
     // check if current block is after start_time
-    if listing.start_time.is_some() && !listing.start_time.unwrap().is_expired(&env.block) {
-        return Err(ContractError::CustomError {
-            val: ("Auction not started".to_string()),
-        });
+    if let Some(start_time) = listing.start_time {
+        if !start_time.is_expired(&env.block) {
+            return Err(ContractError::ListingNotStarted { start_time });
+        }
     }
 
-    if listing.end_time.is_some() && listing.end_time.unwrap().is_expired(&env.block) {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "Auction ended: {} {}",
-                listing.end_time.unwrap(),
-                env.block.time
-            ),
-        });
+    if let Some(end_time) = listing.end_time {
+        if end_time.is_expired(&env.block) {
+            return Err(ContractError::ListingExpired { end_time });
+        }
     }
 
     // message to transfer nft to buyer
@@ -228,24 +400,75 @@ pub fn execute_buy(
         funds: vec![],
     });
 
-    // transfer payment assets to the recipient of listing's consideration
-    let payment_messages = payment_processing(
-        &deps,
-        &info,
-        &PaymentAsset::from(listing.consideration[0].item.clone()),
-        &info.sender,
-        &listing.consideration[0].recipient,
-    )?;
+    // the royalty of collections created by this contract is taken from the price
+    let consideration = listing
+        .consideration
+        .first()
+        .ok_or(ContractError::InvalidPaymentAsset {})?;
+    let price = PaymentAsset::try_from(consideration.item.clone())?;
+    let royalty = COLLECTIONS
+        .may_load(deps.storage, &contract_address)?
+        .and_then(|collection| collection.royalty);
+    let royalty_amount = royalty
+        .as_ref()
+        .map(|royalty| royalty.amount(price.amount()))
+        .unwrap_or(0);
+
+    let mut payouts = vec![(
+        consideration.recipient.clone(),
+        price.amount() - royalty_amount,
+    )];
+    if let Some(royalty) = &royalty {
+        payouts.push((royalty.recipient.clone(), royalty_amount));
+    }
+
+    // transfer payment assets to the recipient of listing's consideration and the royalty recipient
+    let payment_messages = payment_processing(&deps, &info, &price, &info.sender, &payouts)?;
 
     for payment_message in payment_messages {
         res = res.add_message(payment_message);
     }
+    res = res.add_messages(release_listing_bond(&listing, &listing.owner));
+
+    // record the sale in the stats and the sales history
+    update_collection_stats(deps.storage, &contract_address, &price.denom(), |stats| {
+        stats.volume = stats
+            .volume
+            .checked_add(price.amount())
+            .ok_or(ContractError::Overflow {})?;
+        stats.sales_count = stats
+            .sales_count
+            .checked_add(1)
+            .ok_or(ContractError::Overflow {})?;
+        stats.last_sale_price = Some(price.amount());
+        Ok(())
+    })?;
+    let sale_id = SALE_COUNT.may_load(deps.storage)?.unwrap_or(0) + 1;
+    SALE_COUNT.save(deps.storage, &sale_id)?;
+    SALES.save(
+        deps.storage,
+        sale_id,
+        &Sale {
+            sale_id,
+            contract_address: contract_address.clone(),
+            token_id: token_id.clone(),
+            seller: listing.owner.clone(),
+            buyer: info.sender.clone(),
+            price,
+            royalty_amount,
+            height: env.block.height,
+            time: env.block.time,
+        },
+    )?;
 
     Ok(res
-        .add_attribute("method", "buy")
+        .add_event(sale_event(&listing, sale_id, &info.sender, royalty_amount))
+        .add_attribute("action", "buy")
         .add_attribute("contract_address", contract_address.to_string())
         .add_attribute("token_id", token_id.to_string())
-        .add_attribute("buyer", info.sender))
+        .add_attribute("buyer", info.sender)
+        .add_attribute("royalty_amount", royalty_amount.to_string())
+        .add_attribute("sale_id", sale_id.to_string()))
 }
 
 pub fn execute_cancel(
@@ -256,61 +479,168 @@ pub fn execute_cancel(
 ) -> Result<Response, ContractError> {
     let contract_address = asset.contract_address.clone();
     // token_id is required
-    if asset.token_id.is_none() {
-        return Err(ContractError::CustomError {
-            val: "Token ID is required".to_string(),
-        });
-    }
-    let token_id = asset.token_id.unwrap();
+    let token_id = asset.token_id.ok_or(ContractError::TokenIdRequired {})?;
 
     // find listing
     let listing_key = listing_key(&contract_address, &token_id);
     let listing = LISTINGS.load(deps.storage, listing_key.clone())?;
 
-    // if a listing is not expired, only seller can cancel
+    // if a listing is not expired, only seller or a moderator can cancel
     if (!listing.is_expired(&env.block)) && (listing.owner != info.sender) {
-        return Err(ContractError::Unauthorized {});
+        ensure_role(deps.storage, &info.sender, &Role::Moderator)?;
     }
 
     // we will remove the cancelled listing
     LISTINGS.remove(deps.storage, listing_key)?;
+    update_listing_count(deps.storage, &listing, false)?;
+
+    // the bond of an expired listing goes to whoever prunes it
+    let bond_recipient = if listing.is_expired(&env.block) {
+        &info.sender
+    } else {
+        &listing.owner
+    };
 
     Ok(Response::new()
-        .add_attribute("method", "cancel")
+        .add_messages(release_listing_bond(&listing, bond_recipient))
+        .add_event(
+            listing_event(CANCEL_EVENT, &listing).add_attribute("cancelled_by", &info.sender),
+        )
+        .add_attribute("action", "cancel")
         .add_attribute("contract_address", contract_address)
         .add_attribute("token_id", token_id)
         .add_attribute("cancelled_at", env.block.time.to_string()))
 }
 
+// anyone can remove the expired listings, the earliest expired first
+pub fn execute_sweep_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_SWEEP_EXPIRED_LIMIT)
+        .min(MAX_SWEEP_EXPIRED_LIMIT) as usize;
+
+    // the listings expiring at a height are swept before the ones expiring at a time
+    let mut expired: Vec<(ListingKey, Order)> = vec![];
+    for kind in [EXPIRY_AT_HEIGHT, EXPIRY_AT_TIME] {
+        let listings = LISTINGS
+            .idx
+            .end_time
+            .sub_prefix(kind.to_string())
+            .range(deps.storage, None, None, StdOrder::Ascending)
+            .take_while(|item| match item {
+                Ok((_, listing)) => listing.is_expired(&env.block),
+                Err(_) => true,
+            })
+            .take(limit - expired.len())
+            .collect::<StdResult<Vec<_>>>()?;
+        expired.extend(listings);
+    }
+
+    // the bonds of the expired listings are paid to the sweeper
+    let mut res = Response::new();
+    for (listing_key, listing) in expired.iter() {
+        LISTINGS.remove(deps.storage, listing_key.clone())?;
+        update_listing_count(deps.storage, listing, false)?;
+        res = res
+            .add_messages(release_listing_bond(listing, &info.sender))
+            .add_event(
+                listing_event(EXPIRE_EVENT, listing).add_attribute("swept_by", &info.sender),
+            );
+    }
+
+    Ok(res
+        .add_attribute("action", "sweep_expired")
+        .add_attribute("swept", expired.len().to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_create_collection(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     name: String,
     symbol: String,
+    description: Option<String>,
+    image: Option<String>,
+    external_link: Option<String>,
+    max_supply: Option<u64>,
+    royalty_bps: Option<u16>,
+    royalty_recipient: Option<String>,
+    token_id_mode: TokenIdMode,
+    template: String,
 ) -> Result<Response, ContractError> {
-    // load config
-    let config = CONFIG.load(deps.storage)?;
+    // the collection is instantiated from the code id of the template
+    let template = COLLECTION_TEMPLATES
+        .may_load(deps.storage, &template)?
+        .ok_or(ContractError::CollectionTemplateNotFound { name: template })?;
+    let code_id = template.code_id;
+
+    if max_supply == Some(0) {
+        return Err(ContractError::InvalidMaxSupply {});
+    }
+
+    // the royalty is paid to the creator if no recipient is provided,
+    // a royalty of 0 bps means no royalty whatever the recipient
+    let royalty = match (royalty_bps, royalty_recipient) {
+        (Some(bps), _) if bps > MAX_ROYALTY_BPS => {
+            return Err(ContractError::RoyaltyTooHigh {
+                bps,
+                max: MAX_ROYALTY_BPS,
+            });
+        }
+        (Some(bps), recipient) if bps > 0 => Some(Royalty {
+            bps,
+            recipient: match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => info.sender.clone(),
+            },
+        }),
+        (None, Some(_)) => {
+            return Err(ContractError::RoyaltyRecipientWithoutBps {});
+        }
+        _ => None,
+    };
+
     // load collection_id
     let mut collection_id = COLLECTION_ID.load(deps.storage)?;
     // increment collection_id
     collection_id += 1;
-    // save collection_id
 
-    // save a temporary collection_id to storage
-    COLLECTIONS.save(
+    // save the pending collection until the reply of the instantiation
+    PENDING_COLLECTIONS.save(
         deps.storage,
-        collection_id.to_string(),
-        &info.sender.to_string(),
+        collection_id,
+        &PendingCollection {
+            collection: CollectionInfo {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                creator: info.sender.clone(),
+                created_at: env.block.height,
+                description,
+                image,
+                external_link,
+                max_supply,
+                royalty: royalty.clone(),
+                token_id_mode,
+                kind: template.kind,
+                code_id: Some(code_id),
+                code_version: None,
+            },
+        },
     )?;
 
+    // save collection_id
     COLLECTION_ID.save(deps.storage, &collection_id)?;
     Ok(Response::new()
         .add_submessage(SubMsg {
             id: collection_id,
             gas_limit: None,
             msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
-                code_id: config.collection_code_id,
+                code_id,
                 funds: vec![],
                 admin: Some(env.contract.address.to_string()),
                 label: "create collection".to_string(),
@@ -320,12 +650,24 @@ pub fn execute_create_collection(
                     minter: env.contract.address.to_string(),
                 })?,
             }),
-            reply_on: ReplyOn::Success,
+            // the pending collection is also cleaned up when the instantiation fails
+            reply_on: ReplyOn::Always,
         })
-        .add_attribute("method", "create_collection")
+        .add_attribute("action", "create_collection")
+        .add_attribute("template", template.name)
         .add_attribute("name", name)
         .add_attribute("symbol", symbol)
-        .add_attribute("minter", info.sender.to_string()))
+        .add_attribute("minter", info.sender.to_string())
+        .add_attribute(
+            "max_supply",
+            max_supply
+                .map(|max_supply| max_supply.to_string())
+                .unwrap_or_else(|| "unlimited".to_string()),
+        )
+        .add_attribute(
+            "royalty_bps",
+            royalty.map(|royalty| royalty.bps).unwrap_or(0).to_string(),
+        ))
 }
 
 pub fn execute_mint_nft(
@@ -333,42 +675,659 @@ pub fn execute_mint_nft(
     _env: Env,
     info: MessageInfo,
     contract_address: String,
-    token_id: String,
+    token_id: Option<String>,
     token_uri: String,
+    extension: Option<Metadata>,
 ) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Minting)?;
+
     // check if contract address and info.sender are valid
-    let collection_minter = COLLECTIONS.load(deps.storage, contract_address.to_string())?;
-    if collection_minter != info.sender {
-        return Err(ContractError::Unauthorized {});
-    }
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    let collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+    ensure_minter(deps.storage, &collection_address, &collection, &info.sender)?;
 
     // mint nft
-    let transfer_nft_msg = WasmMsg::Execute {
-        contract_addr: contract_address,
-        msg: to_json_binary(&cw721_base::ExecuteMsg::<Extension, Empty>::Mint {
+    let (mint_msg, token_id, minted) = prepare_mint(
+        deps.storage,
+        &collection_address,
+        &collection,
+        token_id,
+        &info.sender,
+        token_uri.clone(),
+        extension,
+    )?;
+
+    Ok(Response::new()
+        .add_message(mint_msg)
+        .add_event(
+            sender_event(CREATOR_MINT_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("token_id", &token_id)
+                .add_attribute("owner", &info.sender),
+        )
+        .add_attribute("action", "mint_nft")
+        .add_attribute("minter", info.sender)
+        .add_attribute("token_id", token_id)
+        .add_attribute("token_uri", token_uri)
+        .add_attribute("total_minted", minted.to_string()))
+}
+
+pub fn execute_batch_mint(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    tokens: Vec<MintItem>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Minting)?;
+
+    // the batch size is bounded to keep the transaction within the gas limit
+    if tokens.is_empty() || tokens.len() > MAX_BATCH_MINT_SIZE as usize {
+        return Err(ContractError::InvalidBatchSize {
+            max_size: MAX_BATCH_MINT_SIZE,
+        });
+    }
+
+    // check if contract address and info.sender are valid
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    let collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+    ensure_minter(deps.storage, &collection_address, &collection, &info.sender)?;
+
+    let count = tokens.len();
+    let mut res = Response::new();
+    let mut minted = 0;
+    for token in tokens {
+        let owner = deps.api.addr_validate(&token.owner)?;
+        let (mint_msg, token_id, total_minted) = prepare_mint(
+            deps.storage,
+            &collection_address,
+            &collection,
+            token.token_id,
+            &owner,
+            token.token_uri,
+            token.extension,
+        )?;
+        res = res.add_message(mint_msg).add_event(
+            sender_event(CREATOR_MINT_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("owner", owner),
+        );
+        minted = total_minted;
+    }
+
+    Ok(res
+        .add_attribute("action", "batch_mint")
+        .add_attribute("minter", info.sender)
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("count", count.to_string())
+        .add_attribute("total_minted", minted.to_string()))
+}
+
+pub fn execute_transfer_collection_creator(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    new_creator: String,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    let mut collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+    if collection.creator != info.sender {
+        ensure_role(deps.storage, &info.sender, &Role::CollectionCurator)?;
+    }
+
+    let previous_creator = collection.creator;
+    collection.creator = deps.api.addr_validate(&new_creator)?;
+    // the royalty paid to the previous creator goes to the new creator,
+    // a royalty paid to another address is unchanged
+    if let Some(royalty) = collection.royalty.as_mut() {
+        if royalty.recipient == previous_creator {
+            royalty.recipient = collection.creator.clone();
+        }
+    }
+    // the creator index is updated with the collection
+    COLLECTIONS.save(deps.storage, &collection_address, &collection)?;
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(TRANSFER_COLLECTION_CREATOR_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("previous_creator", &previous_creator)
+                .add_attribute("new_creator", &collection.creator)
+                .add_attribute(
+                    "royalty_recipient",
+                    optional(
+                        collection
+                            .royalty
+                            .as_ref()
+                            .map(|royalty| &royalty.recipient),
+                    ),
+                ),
+        )
+        .add_attribute("action", "transfer_collection_creator")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("previous_creator", previous_creator)
+        .add_attribute("new_creator", collection.creator))
+}
+
+pub fn execute_add_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    minter: String,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    load_created_collection(&deps, &info, &collection_address)?;
+
+    let minter = deps.api.addr_validate(&minter)?;
+    if COLLECTION_MINTERS.has(deps.storage, (&collection_address, &minter)) {
+        return Err(ContractError::MinterAlreadyExists {
+            minter: minter.to_string(),
+        });
+    }
+    COLLECTION_MINTERS.save(deps.storage, (&collection_address, &minter), &())?;
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(ADD_MINTER_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("minter", &minter),
+        )
+        .add_attribute("action", "add_minter")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("minter", minter))
+}
+
+pub fn execute_remove_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    minter: String,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    load_created_collection(&deps, &info, &collection_address)?;
+
+    let minter = deps.api.addr_validate(&minter)?;
+    COLLECTION_MINTERS.remove(deps.storage, (&collection_address, &minter));
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(REMOVE_MINTER_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("minter", &minter),
+        )
+        .add_attribute("action", "remove_minter")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("minter", minter))
+}
+
+pub fn execute_migrate_collections(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    code_id: u64,
+    collections: Vec<String>,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if collections.is_empty() || collections.len() > MAX_MIGRATE_COLLECTIONS_SIZE as usize {
+        return Err(ContractError::InvalidBatchSize {
+            max_size: MAX_MIGRATE_COLLECTIONS_SIZE,
+        });
+    }
+
+    // the owner can migrate every collection, the creators only their own
+    let config = CONFIG.load(deps.storage)?;
+    let is_owner = config.is_owner(&info.sender);
+
+    // the kind of a code id is only known from the templates using it
+    let template_kinds = COLLECTION_TEMPLATES
+        .range(deps.storage, None, None, StdOrder::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, template)) if template.code_id != code_id => None,
+            item => Some(item.map(|(_, template)| template.kind)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut res = Response::new();
+    for (index, contract_address) in collections.iter().enumerate() {
+        let collection_address = deps.api.addr_validate(contract_address)?;
+        let mut collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+        if !is_owner && collection.creator != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // a soulbound collection must stay soulbound, and no collection can become soulbound,
+        // otherwise its tokens could be listed, or the listed tokens could not be transferred,
+        // a soulbound collection is never migrated to a code id without a template
+        let soulbound = |kind: &CollectionKind| *kind == CollectionKind::Soulbound;
+        let keeps_kind = match template_kinds.is_empty() {
+            true => !soulbound(&collection.kind),
+            false => template_kinds
+                .iter()
+                .all(|kind| soulbound(kind) == soulbound(&collection.kind)),
+        };
+        if !keeps_kind {
+            return Err(ContractError::SoulboundKindChange {
+                contract_address: collection_address.to_string(),
+            });
+        }
+        if let Some(kind) = template_kinds.first() {
+            collection.kind = kind.clone();
+        }
+        collection.code_id = Some(code_id);
+        COLLECTIONS.save(deps.storage, &collection_address, &collection)?;
+
+        // the version of the collection is read in the reply, once the migration has run
+        let reply_id = MIGRATE_COLLECTION_REPLY_ID + index as u64;
+        PENDING_MIGRATIONS.save(deps.storage, reply_id, &collection_address)?;
+
+        // the marketplace is the admin of every collection it created
+        res = res
+            .add_submessage(SubMsg::reply_on_success(
+                WasmMsg::Migrate {
+                    contract_addr: collection_address.to_string(),
+                    new_code_id: code_id,
+                    msg: msg.clone(),
+                },
+                reply_id,
+            ))
+            .add_event(
+                sender_event(MIGRATE_COLLECTION_EVENT, &info.sender)
+                    .add_attribute("contract_address", collection_address)
+                    .add_attribute("code_id", code_id.to_string())
+                    .add_attribute("kind", collection.kind.as_str()),
+            );
+    }
+
+    Ok(res
+        .add_attribute("action", "migrate_collections")
+        .add_attribute("code_id", code_id.to_string())
+        .add_attribute("count", collections.len().to_string()))
+}
+
+// return an error if the address is neither the creator nor a minter of the collection
+fn ensure_minter(
+    storage: &dyn Storage,
+    collection_address: &Addr,
+    collection: &CollectionInfo,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    if collection.creator == *address
+        || COLLECTION_MINTERS.has(storage, (collection_address, address))
+    {
+        return Ok(());
+    }
+    Err(ContractError::Unauthorized {})
+}
+
+// count the new token against the max supply of the collection and create the mint message,
+// return the message, the token id and the number of tokens minted so far
+fn prepare_mint(
+    storage: &mut dyn Storage,
+    collection_address: &Addr,
+    collection: &CollectionInfo,
+    token_id: Option<String>,
+    owner: &Addr,
+    token_uri: String,
+    extension: Option<Metadata>,
+) -> Result<(WasmMsg, String, u64), ContractError> {
+    // only the collections storing metadata on-chain accept an extension
+    if extension.is_some() && collection.kind != CollectionKind::OnchainMetadata {
+        return Err(ContractError::OnchainMetadataNotSupported {
+            contract_address: collection_address.to_string(),
+        });
+    }
+
+    let minted = MINTED_COUNT
+        .may_load(storage, collection_address)?
+        .unwrap_or(0);
+    if let Some(max_supply) = collection.max_supply {
+        if minted >= max_supply {
+            return Err(ContractError::MaxSupplyReached { max_supply });
+        }
+    }
+    let minted = minted + 1;
+    MINTED_COUNT.save(storage, collection_address, &minted)?;
+
+    // sequential token ids are never provided by the minter
+    let token_id = match (&collection.token_id_mode, token_id) {
+        (TokenIdMode::Manual, Some(token_id)) => token_id,
+        (TokenIdMode::Sequential, None) => minted.to_string(),
+        (TokenIdMode::Manual, None) => return Err(ContractError::TokenIdRequired {}),
+        (TokenIdMode::Sequential, Some(_)) => return Err(ContractError::TokenIdNotAllowed {}),
+    };
+
+    let mint_msg = WasmMsg::Execute {
+        contract_addr: collection_address.to_string(),
+        // an empty extension is also accepted by the standard cw721 contract
+        msg: to_json_binary(&cw721_base::ExecuteMsg::<Option<Metadata>, Empty>::Mint {
             token_id: token_id.clone(),
-            owner: info.sender.to_string(),
-            token_uri: Some(token_uri.clone()),
-            extension: None,
+            owner: owner.to_string(),
+            token_uri: Some(token_uri),
+            extension,
         })?,
         funds: vec![],
     };
 
+    Ok((mint_msg, token_id, minted))
+}
+
+pub fn execute_update_protocol_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee_bps: u16,
+    fee_recipient: String,
+) -> Result<Response, ContractError> {
+    // only the owner and the fee managers can update the protocol fee
+    ensure_role(deps.storage, &info.sender, &Role::FeeManager)?;
+
+    if fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(ContractError::ProtocolFeeTooHigh {
+            bps: fee_bps,
+            max: MAX_PROTOCOL_FEE_BPS,
+        });
+    }
+
+    let recipient = deps.api.addr_validate(&fee_recipient)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    config.protocol_fee = Some(ProtocolFee {
+        bps: fee_bps,
+        recipient: recipient.clone(),
+    });
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new()
-        .add_message(transfer_nft_msg)
-        .add_attribute("action", "mint_nft")
+        .add_event(
+            sender_event(UPDATE_PROTOCOL_FEE_EVENT, &info.sender)
+                .add_attribute("fee_bps", fee_bps.to_string())
+                .add_attribute("fee_recipient", &recipient),
+        )
+        .add_attribute("action", "update_protocol_fee")
+        .add_attribute("fee_bps", fee_bps.to_string())
+        .add_attribute("fee_recipient", recipient))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_add_mint_phase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    start_time: Timestamp,
+    end_time: Timestamp,
+    price: PaymentAsset,
+    max_per_wallet: Option<u32>,
+    allowlist: Option<Vec<String>>,
+    base_token_uri: String,
+    merkle_root: Option<String>,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    let collection = load_created_collection(&deps, &info, &collection_address)?;
+
+    // the token ids of the public mints are assigned by the marketplace
+    if collection.token_id_mode != TokenIdMode::Sequential {
+        return Err(ContractError::MintPhaseRequiresSequentialIds { contract_address });
+    }
+    if start_time >= end_time || end_time <= env.block.time {
+        return Err(ContractError::InvalidMintPhaseTime {
+            start_time,
+            end_time,
+        });
+    }
+    if !merkle_root.as_deref().is_none_or(is_valid_merkle_root) {
+        return Err(ContractError::InvalidMerkleRoot {});
+    }
+    if max_per_wallet == Some(0) {
+        return Err(ContractError::InvalidMaxPerWallet {});
+    }
+    if let PaymentAsset::Cw20 {
+        contract_address, ..
+    } = &price
+    {
+        let allowed_tokens = ALLOWED_TOKENS.load(deps.storage)?;
+        if !allowed_tokens.contains(contract_address) {
+            return Err(ContractError::PaymentTokenNotAllowed {
+                contract_address: contract_address.to_string(),
+            });
+        }
+    }
+
+    let phase_id = NEXT_MINT_PHASE_ID
+        .may_load(deps.storage, &collection_address)?
+        .unwrap_or(1);
+    NEXT_MINT_PHASE_ID.save(deps.storage, &collection_address, &(phase_id + 1))?;
+
+    let phase_event = sender_event(ADD_MINT_PHASE_EVENT, &info.sender)
+        .add_attribute("contract_address", &collection_address)
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("start_time", start_time.nanos().to_string())
+        .add_attribute("end_time", end_time.nanos().to_string())
+        .add_attribute("price", price.amount().to_string())
+        .add_attribute("denom", price.denom())
+        .add_attribute("max_per_wallet", optional(max_per_wallet))
+        .add_attribute("has_allowlist", allowlist.is_some().to_string())
+        .add_attribute("merkle_root", optional(merkle_root.as_ref()));
+    MINT_PHASES.save(
+        deps.storage,
+        (&collection_address, phase_id),
+        &MintPhase {
+            start_time,
+            end_time,
+            price,
+            max_per_wallet,
+            has_allowlist: allowlist.is_some(),
+            base_token_uri,
+            merkle_root,
+        },
+    )?;
+    for address in allowlist.unwrap_or_default() {
+        let address = deps.api.addr_validate(&address)?;
+        MINT_PHASE_ALLOWLIST.save(deps.storage, (&collection_address, phase_id, &address), &())?;
+    }
+
+    Ok(Response::new()
+        .add_event(phase_event)
+        .add_attribute("action", "add_mint_phase")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("phase_id", phase_id.to_string()))
+}
+
+pub fn execute_update_mint_phase_allowlist(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    phase_id: u32,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    load_created_collection(&deps, &info, &collection_address)?;
+
+    let mut phase = MINT_PHASES.load(deps.storage, (&collection_address, phase_id))?;
+    for address in add.iter() {
+        let address = deps.api.addr_validate(address)?;
+        MINT_PHASE_ALLOWLIST.save(deps.storage, (&collection_address, phase_id, &address), &())?;
+    }
+    for address in remove.iter() {
+        let address = deps.api.addr_validate(address)?;
+        MINT_PHASE_ALLOWLIST.remove(deps.storage, (&collection_address, phase_id, &address));
+    }
+
+    // a phase becomes restricted once an allowlist is given
+    if !phase.has_allowlist && !add.is_empty() {
+        phase.has_allowlist = true;
+        MINT_PHASES.save(deps.storage, (&collection_address, phase_id), &phase)?;
+    }
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(UPDATE_MINT_PHASE_ALLOWLIST_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("phase_id", phase_id.to_string())
+                .add_attribute("added", add.len().to_string())
+                .add_attribute("removed", remove.len().to_string()),
+        )
+        .add_attribute("action", "update_mint_phase_allowlist")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_remove_mint_phase(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    phase_id: u32,
+) -> Result<Response, ContractError> {
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    load_created_collection(&deps, &info, &collection_address)?;
+
+    if !MINT_PHASES.has(deps.storage, (&collection_address, phase_id)) {
+        return Err(ContractError::MintPhaseNotFound { phase_id });
+    }
+    // the allowlist and wallet counters are kept, the phase id is never reused
+    MINT_PHASES.remove(deps.storage, (&collection_address, phase_id));
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(REMOVE_MINT_PHASE_EVENT, &info.sender)
+                .add_attribute("contract_address", &collection_address)
+                .add_attribute("phase_id", phase_id.to_string()),
+        )
+        .add_attribute("action", "remove_mint_phase")
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("phase_id", phase_id.to_string()))
+}
+
+pub fn execute_public_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    phase_id: u32,
+    proof: Option<MerkleProof>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, &PausableOperation::Minting)?;
+
+    let collection_address = deps.api.addr_validate(&contract_address)?;
+    let collection = COLLECTIONS.load(deps.storage, &collection_address)?;
+    let phase = MINT_PHASES.load(deps.storage, (&collection_address, phase_id))?;
+
+    if !phase.is_active(&env.block) {
+        return Err(ContractError::MintPhaseNotActive { phase_id });
+    }
+
+    // the addresses out of the allowlist can still mint their allocation in the merkle tree
+    let mut max_per_wallet = phase.max_per_wallet;
+    let allowlisted = phase.has_allowlist
+        && MINT_PHASE_ALLOWLIST.has(deps.storage, (&collection_address, phase_id, &info.sender));
+    if (phase.has_allowlist || phase.merkle_root.is_some()) && !allowlisted {
+        match (&phase.merkle_root, proof) {
+            (Some(merkle_root), Some(proof)) => {
+                if !proof.verify(merkle_root, &info.sender) {
+                    return Err(ContractError::InvalidMerkleProof {});
+                }
+                max_per_wallet =
+                    Some(max_per_wallet.map_or(proof.allocation, |max| max.min(proof.allocation)));
+            }
+            _ => return Err(ContractError::NotInAllowlist { phase_id }),
+        }
+    }
+
+    let wallet_mints = MINT_PHASE_WALLET_MINTS
+        .may_load(deps.storage, (&collection_address, phase_id, &info.sender))?
+        .unwrap_or(0);
+    if let Some(max_per_wallet) = max_per_wallet {
+        if wallet_mints >= max_per_wallet {
+            return Err(ContractError::WalletLimitReached { max_per_wallet });
+        }
+    }
+    MINT_PHASE_WALLET_MINTS.save(
+        deps.storage,
+        (&collection_address, phase_id, &info.sender),
+        &(wallet_mints + 1),
+    )?;
+
+    // the token uri is completed with the assigned token id
+    let minted = MINTED_COUNT
+        .may_load(deps.storage, &collection_address)?
+        .unwrap_or(0);
+    let token_uri = format!(
+        "{}/{}",
+        phase.base_token_uri.trim_end_matches('/'),
+        minted + 1
+    );
+    let (mint_msg, token_id, minted) = prepare_mint(
+        deps.storage,
+        &collection_address,
+        &collection,
+        None,
+        &info.sender,
+        token_uri,
+        None,
+    )?;
+
+    // the protocol fee is taken from the price, the creator receives the rest
+    let price = phase.price.amount();
+    let config = CONFIG.load(deps.storage)?;
+    let mut payouts: Vec<(Addr, u128)> = vec![];
+    let mut fee_amount = 0;
+    if let Some(protocol_fee) = config.protocol_fee {
+        fee_amount = protocol_fee.amount(price);
+        payouts.push((protocol_fee.recipient, fee_amount));
+    }
+    payouts.push((collection.creator, price - fee_amount));
+    let payment_messages = payment_processing(&deps, &info, &phase.price, &info.sender, &payouts)?;
+
+    Ok(Response::new()
+        .add_messages(payment_messages)
+        .add_message(mint_msg)
+        .add_event(mint_event(
+            &collection_address,
+            &token_id,
+            phase_id,
+            &info.sender,
+            &phase.price,
+            fee_amount,
+        ))
+        .add_attribute("action", "public_mint")
         .add_attribute("minter", info.sender)
+        .add_attribute("contract_address", collection_address)
+        .add_attribute("phase_id", phase_id.to_string())
         .add_attribute("token_id", token_id)
-        .add_attribute("token_uri", token_uri))
+        .add_attribute("price", price.to_string())
+        .add_attribute("protocol_fee", fee_amount.to_string())
+        .add_attribute("total_minted", minted.to_string()))
+}
+
+// load a collection created by the marketplace, only its creator can manage it
+fn load_created_collection(
+    deps: &DepsMut,
+    info: &MessageInfo,
+    collection_address: &Addr,
+) -> Result<CollectionInfo, ContractError> {
+    let collection = COLLECTIONS.load(deps.storage, collection_address)?;
+    if collection.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(collection)
 }
 
 // function to process payment transfer
+// the price is split between the recipients of the payouts, which must add up to the price
 fn payment_processing(
     deps: &DepsMut,
     info: &MessageInfo,
     asset: &PaymentAsset,
     sender: &Addr,
-    recipient: &Addr,
+    payouts: &[(Addr, u128)],
 ) -> Result<Vec<CosmosMsg>, ContractError> {
     // create empty vector of CosmosMsg
     let mut res_messages: Vec<CosmosMsg> = vec![];
@@ -386,20 +1345,34 @@ fn payment_processing(
         PaymentAsset::Native { denom, amount } => (true, (*denom).clone(), Uint128::from(*amount)),
     };
 
+    // nothing is sent to recipients with an empty payout
+    let payouts = payouts
+        .iter()
+        .filter(|(_, amount)| *amount > 0)
+        .map(|(recipient, amount)| (recipient, Uint128::from(*amount)));
+
     match &is_native {
         false => {
-            // execute cw20 transfer msg from info.sender to recipient
-            let transfer_response = WasmMsg::Execute {
-                contract_addr: deps.api.addr_validate(&token_info).unwrap().to_string(),
-                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
-                    owner: sender.to_string(),
-                    recipient: recipient.to_string(),
-                    amount,
-                })
-                .unwrap(),
-                funds: vec![],
-            };
-            res_messages.push(transfer_response.into());
+            // execute cw20 transfer msg from info.sender to each recipient
+            let contract_addr = deps.api.addr_validate(&token_info)?.to_string();
+            for (recipient, amount) in payouts {
+                let transfer_response = WasmMsg::Execute {
+                    contract_addr: contract_addr.clone(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: sender.to_string(),
+                        recipient: recipient.to_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                };
+                res_messages.push(transfer_response.into());
+            }
+        }
+        // free mints must not send any funds
+        true if amount.is_zero() => {
+            if !info.funds.is_empty() {
+                return Err(ContractError::UnexpectedFunds {});
+            }
         }
         true => {
             let price = Coin {
@@ -407,15 +1380,20 @@ fn payment_processing(
                 amount,
             };
             // check if enough funds
-            if info.funds.is_empty() || info.funds[0] != price {
+            if info.funds.first() != Some(&price) {
                 return Err(ContractError::InsufficientFunds {});
             }
-            // transfer all funds to recipient
-            let transfer_response = BankMsg::Send {
-                to_address: recipient.to_string(),
-                amount: vec![price],
-            };
-            res_messages.push(transfer_response.into());
+            // transfer all funds to the recipients
+            for (recipient, amount) in payouts {
+                let transfer_response = BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![Coin {
+                        denom: token_info.to_string(),
+                        amount,
+                    }],
+                };
+                res_messages.push(transfer_response.into());
+            }
         }
     }
 
@@ -428,11 +1406,8 @@ pub fn execute_allow_payment_token(
     info: MessageInfo,
     contract_address: Addr,
 ) -> Result<Response, ContractError> {
-    // check if sender is the owner
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    // only the owner and the token curators can allow payment tokens
+    ensure_role(deps.storage, &info.sender, &Role::TokenCurator)?;
 
     // check if contract_address is in ALLOWED_TOKENS
     let mut allowed_tokens: Vec<Addr> = ALLOWED_TOKENS.load(deps.storage)?;
@@ -442,6 +1417,424 @@ pub fn execute_allow_payment_token(
     }
 
     Ok(Response::new()
+        .add_event(
+            sender_event(ALLOW_PAYMENT_TOKEN_EVENT, &info.sender)
+                .add_attribute("contract_address", &contract_address),
+        )
         .add_attribute("action", "allow_payment_token")
         .add_attribute("contract_address", contract_address))
 }
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    listing_mode: Option<ListingMode>,
+    listing_bond: Option<Coin>,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(listing_mode) = listing_mode {
+        config.listing_mode = listing_mode;
+    }
+    // a zero bond disables the listing bond
+    if let Some(listing_bond) = listing_bond {
+        config.listing_bond = Some(listing_bond).filter(|bond| !bond.amount.is_zero());
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let bond = config.listing_bond.as_ref();
+    let listing_bond = optional(bond);
+    Ok(Response::new()
+        .add_event(
+            sender_event(UPDATE_CONFIG_EVENT, &info.sender)
+                .add_attribute("listing_mode", config.listing_mode.as_str())
+                .add_attribute(
+                    "listing_bond_amount",
+                    optional(bond.map(|bond| bond.amount)),
+                )
+                .add_attribute("listing_bond_denom", optional(bond.map(|bond| &bond.denom))),
+        )
+        .add_attribute("action", "update_config")
+        .add_attribute("listing_mode", config.listing_mode.as_str())
+        .add_attribute("listing_bond", listing_bond))
+}
+
+pub fn execute_set_collection_template(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+    code_id: u64,
+    kind: CollectionKind,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // setting an existing template again replaces it for the next collections
+    let template = CollectionTemplate {
+        name: name.clone(),
+        code_id,
+        kind,
+    };
+    COLLECTION_TEMPLATES.save(deps.storage, &name, &template)?;
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(SET_COLLECTION_TEMPLATE_EVENT, &info.sender)
+                .add_attribute("name", &name)
+                .add_attribute("code_id", code_id.to_string())
+                .add_attribute("kind", template.kind.as_str()),
+        )
+        .add_attribute("action", "set_collection_template")
+        .add_attribute("name", name)
+        .add_attribute("code_id", code_id.to_string())
+        .add_attribute("kind", template.kind.as_str()))
+}
+
+pub fn execute_remove_collection_template(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !COLLECTION_TEMPLATES.has(deps.storage, &name) {
+        return Err(ContractError::CollectionTemplateNotFound { name });
+    }
+    COLLECTION_TEMPLATES.remove(deps.storage, &name);
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(REMOVE_COLLECTION_TEMPLATE_EVENT, &info.sender)
+                .add_attribute("name", &name),
+        )
+        .add_attribute("action", "remove_collection_template")
+        .add_attribute("name", name))
+}
+
+pub fn execute_register_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+    verified: bool,
+    display_name: String,
+    payment_denoms: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    // only the owner and the collection curators can manage the registry
+    ensure_role(deps.storage, &info.sender, &Role::CollectionCurator)?;
+
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let registry_event = sender_event(REGISTER_COLLECTION_EVENT, &info.sender)
+        .add_attribute("contract_address", &contract_address)
+        .add_attribute("verified", verified.to_string())
+        .add_attribute(
+            "display_name",
+            optional(Some(&display_name).filter(|name| !name.is_empty())),
+        )
+        .add_attribute(
+            "payment_denoms",
+            // "any" if every denom is accepted, "none" if no denom is accepted
+            match &payment_denoms {
+                Some(denoms) => {
+                    optional(Some(denoms.join(",")).filter(|denoms| !denoms.is_empty()))
+                }
+                None => "any".to_string(),
+            },
+        );
+    // registering an existing collection again replaces its registration
+    COLLECTION_REGISTRY.save(
+        deps.storage,
+        &contract_address,
+        &CollectionRegistration {
+            verified,
+            display_name: display_name.clone(),
+            payment_denoms,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_event(registry_event)
+        .add_attribute("action", "register_collection")
+        .add_attribute("contract_address", contract_address)
+        .add_attribute("verified", verified.to_string())
+        .add_attribute("display_name", display_name))
+}
+
+pub fn execute_unregister_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_address: String,
+) -> Result<Response, ContractError> {
+    // only the owner and the collection curators can manage the registry
+    ensure_role(deps.storage, &info.sender, &Role::CollectionCurator)?;
+
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    COLLECTION_REGISTRY.remove(deps.storage, &contract_address);
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(UNREGISTER_COLLECTION_EVENT, &info.sender)
+                .add_attribute("contract_address", &contract_address),
+        )
+        .add_attribute("action", "unregister_collection")
+        .add_attribute("contract_address", contract_address))
+}
+
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // a new proposal replaces the previous one
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(PROPOSE_OWNER_EVENT, &info.sender).add_attribute("new_owner", &new_owner),
+        )
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("owner", info.sender)
+        .add_attribute("new_owner", new_owner))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // only the proposed owner can accept the ownership
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if info.sender != pending_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_owner = config.owner.replace(pending_owner.clone());
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    let previous_owner = optional(previous_owner);
+    Ok(Response::new()
+        .add_event(
+            sender_event(ACCEPT_OWNERSHIP_EVENT, &info.sender)
+                .add_attribute("previous_owner", &previous_owner)
+                .add_attribute("new_owner", &pending_owner),
+        )
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("new_owner", pending_owner))
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // remove the owner and any pending proposal
+    config.owner = None;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_event(sender_event(RENOUNCE_OWNERSHIP_EVENT, &info.sender))
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("previous_owner", info.sender))
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &address), &())?;
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(GRANT_ROLE_EVENT, &info.sender)
+                .add_attribute("role", role.as_str())
+                .add_attribute("address", &address),
+        )
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    // check if sender is the owner
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_owner(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_event(
+            sender_event(REVOKE_ROLE_EVENT, &info.sender)
+                .add_attribute("role", role.as_str())
+                .add_attribute("address", &address),
+        )
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operations: Vec<PausableOperation>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // only the owner and the pausers can pause or unpause
+    ensure_role(deps.storage, &info.sender, &Role::Pauser)?;
+
+    let mut pause_status = PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default();
+    for operation in operations.iter() {
+        pause_status.set(operation, paused);
+    }
+    PAUSE_STATUS.save(deps.storage, &pause_status)?;
+
+    let operations = operations
+        .iter()
+        .map(|operation| operation.as_str())
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    let pause_event = if paused { PAUSE_EVENT } else { UNPAUSE_EVENT };
+    Ok(Response::new()
+        .add_event(sender_event(pause_event, &info.sender).add_attribute(
+            "operations",
+            optional(Some(&operations).filter(|operations| !operations.is_empty())),
+        ))
+        .add_attribute("action", if paused { "pause" } else { "unpause" })
+        .add_attribute("operations", operations)
+        .add_attribute("sender", info.sender))
+}
+
+// the cw2 version of a collection contract, None if the contract does not store it
+pub(crate) fn collection_version(
+    querier: &QuerierWrapper,
+    contract_address: &Addr,
+) -> Option<String> {
+    cw2::query_contract_info(querier, contract_address)
+        .ok()
+        .map(|contract_version| contract_version.version)
+}
+
+// pay the bond of a removed listing to the recipient
+fn release_listing_bond(listing: &Order, recipient: &Addr) -> Option<BankMsg> {
+    listing.bond.clone().map(|bond| BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: vec![bond],
+    })
+}
+
+// update the stats of a collection for one payment denom
+fn update_collection_stats(
+    storage: &mut dyn Storage,
+    contract_address: &Addr,
+    denom: &str,
+    update: impl FnOnce(&mut CollectionStats) -> Result<(), ContractError>,
+) -> Result<(), ContractError> {
+    let mut stats = COLLECTION_STATS
+        .may_load(storage, (contract_address, denom))?
+        .unwrap_or_default();
+    update(&mut stats)?;
+    Ok(COLLECTION_STATS.save(storage, (contract_address, denom), &stats)?)
+}
+
+// count a listing in, or out of, the stored listings of its collection
+pub(crate) fn update_listing_count(
+    storage: &mut dyn Storage,
+    listing: &Order,
+    added: bool,
+) -> Result<(), ContractError> {
+    let (denom, _) = listing.price_key();
+    update_collection_stats(storage, &listing.order_id.1, &denom, |stats| {
+        if added {
+            stats.stored_listing_count = stats
+                .stored_listing_count
+                .checked_add(1)
+                .ok_or(ContractError::Overflow {})?;
+        } else {
+            // the count never goes below zero
+            stats.stored_listing_count = stats.stored_listing_count.saturating_sub(1);
+        }
+        Ok(())
+    })
+}
+
+// return an error if the operation is currently paused
+fn ensure_not_paused(
+    storage: &dyn Storage,
+    operation: &PausableOperation,
+) -> Result<(), ContractError> {
+    let pause_status = PAUSE_STATUS.may_load(storage)?.unwrap_or_default();
+    if pause_status.is_paused(operation) {
+        return Err(ContractError::OperationPaused {
+            operation: operation.as_str().to_string(),
+        });
+    }
+    Ok(())
+}
+
+// return an error if the address is neither the owner nor a holder of the role
+pub fn ensure_role(
+    storage: &dyn Storage,
+    address: &Addr,
+    role: &Role,
+) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    if config.is_owner(address) || ROLES.has(storage, (role.as_str(), address)) {
+        return Ok(());
+    }
+    Err(ContractError::Unauthorized {})
+}
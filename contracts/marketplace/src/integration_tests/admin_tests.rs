@@ -0,0 +1,655 @@
+use crate::msg::{ExecuteMsg, QueryMsg};
+use crate::structs::{CollectionKind, CollectionTemplatesResponse, Config, ListingMode};
+use crate::test_setup::env::{instantiate_contracts, OWNER, USER_1, USER_2};
+use cosmwasm_std::Addr;
+use cw_multi_test::Executor;
+
+mod ownership {
+    use super::*;
+
+    #[test]
+    fn new_owner_must_accept_ownership() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 cannot propose a new owner
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::ProposeNewOwner {
+                new_owner: USER_1.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
+        // OWNER proposes USER_1 as the new owner
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::ProposeNewOwner {
+                new_owner: USER_1.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // the owner is unchanged until the proposal is accepted
+        let config: Config = app
+            .wrap()
+            .query_wasm_smart(marketplace_address.clone(), &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.owner, Some(Addr::unchecked(OWNER)));
+
+        // USER_2 cannot accept the ownership
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AcceptOwnership {},
+            &[],
+        );
+        assert!(res.is_err());
+
+        // USER_1 accepts the ownership
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AcceptOwnership {},
+            &[],
+        );
+        let event = res
+            .unwrap()
+            .events
+            .into_iter()
+            .find(|event| event.ty == "wasm-marketplace-accept-ownership")
+            .unwrap();
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "previous_owner" && attr.value == OWNER));
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "new_owner" && attr.value == USER_1));
+
+        let config: Config = app
+            .wrap()
+            .query_wasm_smart(marketplace_address.clone(), &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.owner, Some(Addr::unchecked(USER_1)));
+
+        // the previous owner cannot update the config anymore
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::UpdateConfig {
+                listing_mode: Some(ListingMode::VerifiedOnly),
+                listing_bond: None,
+            },
+            &[],
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn owner_can_renounce_ownership() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // OWNER adds a soulbound collection template
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::SetCollectionTemplate {
+                name: "soulbound".to_string(),
+                code_id: 100,
+                kind: CollectionKind::Soulbound,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // OWNER renounces the ownership
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::RenounceOwnership {},
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let config: Config = app
+            .wrap()
+            .query_wasm_smart(marketplace_address.clone(), &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.owner, None);
+        let res: CollectionTemplatesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::CollectionTemplates {},
+            )
+            .unwrap();
+        assert_eq!(res.templates.len(), 3);
+
+        // no one can administer the contract anymore
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::ProposeNewOwner {
+                new_owner: OWNER.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+    }
+}
+
+mod pause {
+    use super::*;
+    use crate::structs::{
+        AuctionConfig, NftAsset, PausableOperation, PauseStatus, PaymentAsset, Role,
+    };
+    use crate::test_setup::env::NATIVE_DENOM;
+    use cosmwasm_std::Coin;
+
+    #[test]
+    fn paused_operations_are_blocked_but_cancel_is_allowed() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, mints and lists token 1
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked("contract3".to_string()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let asset = NftAsset {
+            contract_address: Addr::unchecked("contract3".to_string()),
+            token_id: Some("1".to_string()),
+        };
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::ListNft {
+                asset: asset.clone(),
+                listing_config: AuctionConfig::FixedPrice {
+                    price: PaymentAsset::Native {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: 100u128,
+                    },
+                    start_time: None,
+                    end_time: None,
+                    merkle_root: None,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        // USER_2 is not allowed to pause yet
+        let pause_msg = ExecuteMsg::Pause {
+            operations: vec![PausableOperation::Buying, PausableOperation::Minting],
+        };
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &pause_msg,
+            &[],
+        );
+        assert!(res.is_err());
+
+        // OWNER designates USER_2 as pauser, then USER_2 pauses buying and minting
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: USER_2.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &pause_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let pause_status: PauseStatus = app
+            .wrap()
+            .query_wasm_smart(marketplace_address.clone(), &QueryMsg::PauseStatus {})
+            .unwrap();
+        assert_eq!(
+            pause_status,
+            PauseStatus {
+                listing: false,
+                buying: true,
+                minting: true,
+            }
+        );
+
+        // minting and buying are blocked
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("2".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        );
+        assert!(res.is_err());
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Buy {
+                asset: asset.clone(),
+                proof: None,
+            },
+            &[Coin {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 100u128.into(),
+            }],
+        );
+        assert!(res.is_err());
+
+        // USER_1 can still cancel the listing
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Cancel { asset },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_2 unpauses minting
+        app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Unpause {
+                operations: vec![PausableOperation::Minting],
+            },
+            &[],
+        )
+        .unwrap();
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("2".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+    }
+}
+
+mod roles {
+    use super::*;
+    use crate::structs::{Role, RoleHoldersResponse};
+
+    #[test]
+    fn owner_can_grant_and_revoke_roles() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let cw20_address = contracts[2].contract_addr.clone();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 cannot grant roles
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::GrantRole {
+                role: Role::TokenCurator,
+                address: USER_1.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
+        // OWNER grants the token curator role to USER_1 and USER_2
+        for user in [USER_1, USER_2] {
+            let res = app.execute_contract(
+                Addr::unchecked(OWNER.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::GrantRole {
+                    role: Role::TokenCurator,
+                    address: user.to_string(),
+                },
+                &[],
+            );
+            let event = res
+                .unwrap()
+                .events
+                .into_iter()
+                .find(|event| event.ty == "wasm-marketplace-grant-role")
+                .unwrap();
+            assert!(event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "role" && attr.value == "token_curator"));
+            assert!(event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "address" && attr.value == user));
+        }
+
+        let res: RoleHoldersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::RoleHolders {
+                    role: Role::TokenCurator,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.holders,
+            vec![Addr::unchecked(USER_1), Addr::unchecked(USER_2)]
+        );
+
+        // USER_1 allows the cw20 token without the owner key
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AllowPaymentToken {
+                contract_address: Addr::unchecked(cw20_address.clone()),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // OWNER revokes the role from USER_1
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::RevokeRole {
+                role: Role::TokenCurator,
+                address: USER_1.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AllowPaymentToken {
+                contract_address: Addr::unchecked(cw20_address),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
+        let res: RoleHoldersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::RoleHolders {
+                    role: Role::TokenCurator,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.holders, vec![Addr::unchecked(USER_2)]);
+    }
+}
+
+mod registry {
+    use super::*;
+    use crate::structs::{
+        AuctionConfig, ListingMode, ListingResponse, NftAsset, PaymentAsset, Role,
+    };
+    use crate::test_setup::env::{NATIVE_DENOM, NATIVE_DENOM_2};
+    use crate::ContractError;
+    use cw721_base::{msg::ExecuteMsg as Cw721ExecuteMsg, Extension};
+
+    fn list_msg(contract_address: &str, denom: &str) -> ExecuteMsg {
+        ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked(contract_address),
+                token_id: Some("1".to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Native {
+                    denom: denom.to_string(),
+                    amount: 100u128,
+                },
+                start_time: None,
+                end_time: None,
+                merkle_root: None,
+            },
+        }
+    }
+
+    #[test]
+    fn only_verified_collections_can_be_listed_in_verified_only_mode() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let cw721_address = contracts[0].contract_addr.clone();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // OWNER mints token 1 of the external collection to USER_1, who approves the marketplace
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(cw721_address.clone()),
+            &Cw721ExecuteMsg::<Extension, cosmwasm_std::Empty>::Mint {
+                token_id: "1".to_string(),
+                owner: USER_1.to_string(),
+                token_uri: None,
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(cw721_address.clone()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // OWNER only allows verified collections
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::UpdateConfig {
+                listing_mode: Some(ListingMode::VerifiedOnly),
+                listing_bond: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // the collection is not verified yet
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_msg(&cw721_address, NATIVE_DENOM),
+            &[],
+        );
+        assert!(res.is_err());
+
+        // USER_2 is a collection curator and verifies the collection, priced in NATIVE_DENOM only
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::GrantRole {
+                role: Role::CollectionCurator,
+                address: USER_2.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::RegisterCollection {
+                contract_address: cw721_address.clone(),
+                verified: true,
+                display_name: "NFT A".to_string(),
+                payment_denoms: Some(vec![NATIVE_DENOM.to_string()]),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // the collection does not accept NATIVE_DENOM_2
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_msg(&cw721_address, NATIVE_DENOM_2),
+            &[],
+        );
+        assert!(res.is_err());
+
+        // an offer price listing is rejected as unsupported, not for its denom
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER_1.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::ListNft {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked(cw721_address.clone()),
+                        token_id: Some("1".to_string()),
+                    },
+                    listing_config: AuctionConfig::OfferPrice {
+                        price: NftAsset {
+                            contract_address: Addr::unchecked(cw721_address.clone()),
+                            token_id: Some("2".to_string()),
+                        },
+                        start_time: None,
+                        end_time: None,
+                    },
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::UnsupportedAuctionConfig {}
+        ));
+
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_msg(&cw721_address, NATIVE_DENOM),
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // the listing is reported as verified
+        let res: ListingResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::Listing {
+                    contract_address: Addr::unchecked(cw721_address),
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(res.verified);
+    }
+}
+
+mod templates {
+    use super::*;
+    use crate::contract::instantiate;
+    use crate::msg::InstantiateMsg;
+    use crate::structs::CollectionTemplate;
+    use crate::ContractError;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    fn template(name: &str, code_id: u64) -> CollectionTemplate {
+        CollectionTemplate {
+            name: name.to_string(),
+            code_id,
+            kind: CollectionKind::Standard,
+        }
+    }
+
+    fn instantiate_with(
+        collection_templates: Vec<CollectionTemplate>,
+    ) -> Result<(), ContractError> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Addr::unchecked(OWNER),
+            collection_templates,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).map(|_| ())
+    }
+
+    #[test]
+    fn instantiate_validates_collection_templates() {
+        assert!(matches!(
+            instantiate_with(vec![]),
+            Err(ContractError::NoCollectionTemplates {})
+        ));
+        assert!(matches!(
+            instantiate_with(vec![template("standard", 1), template("standard", 2)]),
+            Err(ContractError::DuplicateCollectionTemplate { name }) if name == "standard"
+        ));
+        // the default template is required
+        assert!(matches!(
+            instantiate_with(vec![template("custom", 1)]),
+            Err(ContractError::CollectionTemplateNotFound { name }) if name == "standard"
+        ));
+        instantiate_with(vec![template("standard", 1), template("custom", 2)]).unwrap();
+    }
+}
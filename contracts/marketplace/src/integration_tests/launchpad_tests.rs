@@ -0,0 +1,502 @@
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw_multi_test::{App, Executor};
+
+use crate::msg::{ExecuteMsg, QueryMsg};
+use crate::structs::{MintPhasesResponse, PaymentAsset, TokenIdMode, WalletMintsResponse};
+use crate::test_setup::env::{
+    instantiate_contracts, native_balance, NATIVE_DENOM, OWNER, USER_1, USER_2,
+};
+
+const FEE_COLLECTOR: &str = "fee_collector";
+
+mod public_mint {
+    use super::*;
+
+    #[test]
+    fn user_can_buy_tokens_in_allowlisted_phase() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // OWNER takes 10% of the primary sales
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::UpdateProtocolFee {
+                fee_bps: 1_000,
+                fee_recipient: FEE_COLLECTOR.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // USER_1 creates collection with sequential token ids
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: Some(100),
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: Some(TokenIdMode::Sequential),
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // USER_1 opens a phase for USER_2 only, 1 token per wallet at 100
+        let now = app.block_info().time;
+        let add_phase_msg = ExecuteMsg::AddMintPhase {
+            contract_address: "contract3".to_string(),
+            start_time: now.plus_seconds(100),
+            end_time: now.plus_seconds(1_000),
+            price: PaymentAsset::Native {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 100u128,
+            },
+            max_per_wallet: Some(1),
+            allowlist: Some(vec![USER_2.to_string()]),
+            base_token_uri: "ipfs://collection-a/".to_string(),
+            merkle_root: None,
+        };
+
+        // only the creator can add a phase
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &add_phase_msg,
+            &[],
+        );
+        assert!(res.is_err());
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &add_phase_msg,
+            &[],
+        )
+        .unwrap();
+
+        let res: MintPhasesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::MintPhases {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.phases.len(), 1);
+        assert_eq!(res.phases[0].phase_id, 1);
+
+        let public_mint_msg = ExecuteMsg::PublicMint {
+            contract_address: "contract3".to_string(),
+            phase_id: 1,
+            proof: None,
+        };
+        let price = [Coin {
+            denom: NATIVE_DENOM.to_string(),
+            amount: 100u128.into(),
+        }];
+
+        // the phase has not started yet
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg,
+            &price,
+        );
+        assert!(res.is_err());
+
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+        // USER_1 is not in the allowlist
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg,
+            &price,
+        );
+        assert!(res.is_err());
+
+        // the full price must be paid
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg,
+            &[Coin {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 50u128.into(),
+            }],
+        );
+        assert!(res.is_err());
+
+        let creator_balance_before = native_balance(&app, USER_1);
+        app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg,
+            &price,
+        )
+        .unwrap();
+
+        // the creator receives 90 and the fee recipient receives 10
+        assert_eq!(
+            native_balance(&app, USER_1),
+            creator_balance_before + Uint128::from(90u128)
+        );
+        assert_eq!(native_balance(&app, FEE_COLLECTOR), Uint128::from(10u128));
+
+        // token 1 belongs to USER_2 and points to the phase base uri
+        let res: cw721::NftInfoResponse<Option<cosmwasm_std::Empty>> = app
+            .wrap()
+            .query_wasm_smart(
+                "contract3",
+                &cw721::Cw721QueryMsg::NftInfo {
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.token_uri, Some("ipfs://collection-a/1".to_string()));
+        let owner: cw721::OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(
+                "contract3",
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "1".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(owner.owner, USER_2);
+
+        let res: WalletMintsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::WalletMints {
+                    contract_address: "contract3".to_string(),
+                    phase_id: 1,
+                    address: USER_2.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.minted, 1);
+        assert!(res.allowlisted);
+
+        // USER_2 reached the wallet limit
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg,
+            &price,
+        );
+        assert!(res.is_err());
+
+        // the phase is over
+        app.update_block(|block| block.time = block.time.plus_seconds(1_000));
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::UpdateMintPhaseAllowlist {
+                contract_address: "contract3".to_string(),
+                phase_id: 1,
+                add: vec![USER_1.to_string()],
+                remove: vec![],
+            },
+            &[],
+        )
+        .unwrap();
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address),
+            &public_mint_msg,
+            &price,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn fee_manager_cannot_exceed_max_protocol_fee() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 is not a fee manager
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::UpdateProtocolFee {
+                fee_bps: 100,
+                fee_recipient: FEE_COLLECTOR.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::UpdateProtocolFee {
+                fee_bps: 5_000,
+                fee_recipient: FEE_COLLECTOR.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_err());
+    }
+}
+
+mod merkle_allowlist {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::structs::{AuctionConfig, MerkleProof, NftAsset};
+
+    fn leaf(address: &str, allocation: u32) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(address.as_bytes())
+            .chain_update(allocation.to_be_bytes())
+            .finalize()
+            .into()
+    }
+
+    // the root of a tree of two leaves
+    fn root(first: [u8; 32], second: [u8; 32]) -> String {
+        let (first, second) = if first <= second {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        hex::encode(
+            Sha256::new()
+                .chain_update(first)
+                .chain_update(second)
+                .finalize(),
+        )
+    }
+
+    fn create_sequential_collection(app: &mut App, marketplace_address: &str) {
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: Some(TokenIdMode::Sequential),
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn user_can_mint_allocation_with_merkle_proof() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+        create_sequential_collection(&mut app, &marketplace_address);
+
+        // USER_2 can mint 2 free tokens and OWNER 1
+        let user_2_leaf = leaf(USER_2, 2);
+        let owner_leaf = leaf(OWNER, 1);
+        let now = app.block_info().time;
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AddMintPhase {
+                contract_address: "contract3".to_string(),
+                start_time: now,
+                end_time: now.plus_seconds(1_000),
+                price: PaymentAsset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: 0u128,
+                },
+                max_per_wallet: None,
+                allowlist: None,
+                base_token_uri: "ipfs://collection-a".to_string(),
+                merkle_root: Some(root(user_2_leaf, owner_leaf)),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let public_mint_msg = |allocation: u32, sibling: [u8; 32]| ExecuteMsg::PublicMint {
+            contract_address: "contract3".to_string(),
+            phase_id: 1,
+            proof: Some(MerkleProof {
+                allocation,
+                proof: vec![hex::encode(sibling)],
+            }),
+        };
+
+        // USER_2 cannot claim a larger allocation
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg(3, owner_leaf),
+            &[],
+        );
+        assert!(res.is_err());
+
+        // USER_1 is not in the tree
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::PublicMint {
+                contract_address: "contract3".to_string(),
+                phase_id: 1,
+                proof: None,
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
+        // USER_2 mints the 2 tokens of the allocation
+        for _ in 0..2 {
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                Addr::unchecked(marketplace_address.clone()),
+                &public_mint_msg(2, owner_leaf),
+                &[],
+            )
+            .unwrap();
+        }
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &public_mint_msg(2, owner_leaf),
+            &[],
+        );
+        assert!(res.is_err());
+
+        let res: WalletMintsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::WalletMints {
+                    contract_address: "contract3".to_string(),
+                    phase_id: 1,
+                    address: USER_2.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.minted, 2);
+    }
+
+    #[test]
+    fn only_buyers_in_merkle_tree_can_buy_private_listing() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+        create_sequential_collection(&mut app, &marketplace_address);
+
+        // USER_1 mints, approves and lists token 1 for USER_2 only
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: None,
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked("contract3"),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let user_2_leaf = leaf(USER_2, 1);
+        let owner_leaf = leaf(OWNER, 1);
+        let asset = NftAsset {
+            contract_address: Addr::unchecked("contract3"),
+            token_id: Some("1".to_string()),
+        };
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::ListNft {
+                asset: asset.clone(),
+                listing_config: AuctionConfig::FixedPrice {
+                    price: PaymentAsset::Native {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: 100u128,
+                    },
+                    start_time: None,
+                    end_time: None,
+                    merkle_root: Some(root(user_2_leaf, owner_leaf)),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let price = [Coin {
+            denom: NATIVE_DENOM.to_string(),
+            amount: 100u128.into(),
+        }];
+
+        // OWNER cannot use the proof of USER_2
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Buy {
+                asset: asset.clone(),
+                proof: Some(MerkleProof {
+                    allocation: 1,
+                    proof: vec![hex::encode(owner_leaf)],
+                }),
+            },
+            &price,
+        );
+        assert!(res.is_err());
+
+        app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::Buy {
+                asset,
+                proof: Some(MerkleProof {
+                    allocation: 1,
+                    proof: vec![hex::encode(owner_leaf)],
+                }),
+            },
+            &price,
+        )
+        .unwrap();
+
+        let owner: cw721::OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(
+                "contract3",
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "1".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(owner.owner, USER_2);
+    }
+}
@@ -21,8 +21,61 @@ use cosmwasm_std::Addr;
 // const MOCK_OFFER_NFT_OFFERER_INSUFFICIENT_BALANCE: &str = "offerer 2";
 // const MOCK_OFFER_NFT_OFFERER_INSUFFICIENT_ALLOWANCE: &str = "offerer 3";
 
+// approve and list the token for the price, at the next block
+fn list(
+    app: &mut cw_multi_test::App,
+    marketplace_address: &str,
+    seller: &str,
+    contract_address: &str,
+    token_id: &str,
+    price: u128,
+    end_time: Option<cw721::Expiration>,
+) {
+    app.update_block(|block| block.height += 1);
+    app.execute_contract(
+        Addr::unchecked(seller),
+        Addr::unchecked(contract_address),
+        &cw721::Cw721ExecuteMsg::Approve {
+            spender: marketplace_address.to_string(),
+            token_id: token_id.to_string(),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(seller),
+        Addr::unchecked(marketplace_address),
+        &ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked(contract_address),
+                token_id: Some(token_id.to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: price,
+                },
+                start_time: None,
+                end_time,
+                merkle_root: None,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+}
+
 mod create_collection {
     use super::*;
+    use crate::error::ContractError;
+    use crate::msg::QueryMsg;
+    use crate::structs::{
+        CollectionKind, CollectionMintersResponse, CollectionResponse, CollectionTemplatesResponse,
+        CollectionsResponse, Metadata, MintItem, TokenIdMode, Trait,
+    };
+    use crate::test_setup::env::{cw721_upgraded_contract_template, OWNER};
+    use cosmwasm_std::Empty;
 
     #[test]
     fn user_can_create_own_collection() {
@@ -35,16 +88,47 @@ mod create_collection {
         let create_collection_msg = ExecuteMsg::CreateCollection {
             name: "NFT_A".to_string(),
             symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: None,
         };
 
         // USER_1 creates collection
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
-            Addr::unchecked(marketplace_address),
+            Addr::unchecked(marketplace_address.clone()),
             &create_collection_msg,
             &[],
         );
         assert!(res.is_ok());
+
+        // the collection can be found by its creator
+        let res: CollectionsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::CollectionsByCreator {
+                    creator: USER_1.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collections.len(), 1);
+        assert_eq!(
+            res.collections[0].contract_address,
+            Addr::unchecked("contract3")
+        );
+        assert_eq!(res.collections[0].collection.name, "NFT_A");
+        assert_eq!(
+            res.collections[0].collection.creator,
+            Addr::unchecked(USER_1)
+        );
     }
 
     #[test]
@@ -61,6 +145,14 @@ mod create_collection {
         let create_collection_msg = ExecuteMsg::CreateCollection {
             name: "NFT_A".to_string(),
             symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: None,
         };
 
         // USER_1 creates collection
@@ -76,8 +168,9 @@ mod create_collection {
         // prepare mint token message
         let mint_token_msg = ExecuteMsg::MintNft {
             contract_address: "contract3".to_string(),
-            token_id: "1".to_string(),
+            token_id: Some("1".to_string()),
             token_uri: "https://www.google.com".to_string(),
+            extension: None,
         };
 
         // USER_1 mints token
@@ -89,278 +182,406 @@ mod create_collection {
         );
         assert!(res.is_ok());
     }
-}
-
-mod listing_nft {
-    use cosmwasm_std::{Coin, Uint128};
-    use cw20::BalanceResponse;
-
-    use crate::test_setup::env::OWNER;
-
-    use super::*;
 
     #[test]
-    fn user_can_list_nft_for_sale() {
+    fn sequential_token_ids_are_capped_by_max_supply() {
         // get integration test app and contracts
         let (mut app, contracts) = instantiate_contracts();
-        // let cw721_address = contracts[0].contract_addr.clone();
         let marketplace_address = contracts[1].contract_addr.clone();
 
-        // prepare create collection message
-        let create_collection_msg = ExecuteMsg::CreateCollection {
-            name: "NFT_A".to_string(),
-            symbol: "NFT".to_string(),
-        };
-
-        // USER_1 creates collection
+        // USER_1 creates collection of 2 tokens with sequential token ids
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &create_collection_msg,
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: Some(2),
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: Some(TokenIdMode::Sequential),
+                template: None,
+            },
             &[],
         );
         assert!(res.is_ok());
 
-        // prepare mint token message
+        // the token id cannot be chosen by the minter
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("10".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        );
+        assert!(res.is_err());
+
         let mint_token_msg = ExecuteMsg::MintNft {
             contract_address: "contract3".to_string(),
-            token_id: "1".to_string(),
+            token_id: None,
             token_uri: "https://www.google.com".to_string(),
+            extension: None,
         };
+        for expected_token_id in ["1", "2"] {
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(USER_1.to_string()),
+                    Addr::unchecked(marketplace_address.clone()),
+                    &mint_token_msg,
+                    &[],
+                )
+                .unwrap();
+            let wasm = res.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+            let attribute = |key: &str| {
+                wasm.attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .unwrap()
+                    .value
+                    .clone()
+            };
+            assert_eq!(attribute("token_id"), expected_token_id);
+            assert_eq!(attribute("total_minted"), expected_token_id);
+        }
 
-        // USER_1 mints token
+        // the max supply is reached
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
             &mint_token_msg,
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // prepare list nft message
-        let list_nft_msg = ExecuteMsg::ListNft {
-            asset: NftAsset {
-                contract_address: Addr::unchecked("contract3".to_string()),
-                token_id: Some("1".to_string()),
-            },
-            listing_config: AuctionConfig::FixedPrice {
-                price: PaymentAsset::Native {
-                    denom: NATIVE_DENOM.to_string(),
-                    amount: 100u128.into(),
+        // token 2 belongs to USER_1
+        let owner: cw721::OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(
+                "contract3",
+                &cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: "2".to_string(),
+                    include_expired: None,
                 },
-                start_time: None,
-                end_time: None,
-            },
+            )
+            .unwrap();
+        assert_eq!(owner.owner, USER_1);
+
+        let res: CollectionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::Collection {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.minted, 2);
+    }
+    #[test]
+    fn user_can_mint_token_with_onchain_metadata() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates a standard collection and a collection storing metadata on-chain
+        for template in [None, Some("onchain-metadata".to_string())] {
+            let res = app.execute_contract(
+                Addr::unchecked(USER_1.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::CreateCollection {
+                    name: "NFT_A".to_string(),
+                    symbol: "NFT".to_string(),
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    max_supply: None,
+                    royalty_bps: None,
+                    royalty_recipient: None,
+                    token_id_mode: None,
+                    template,
+                },
+                &[],
+            );
+            assert!(res.is_ok());
+        }
+
+        let metadata = Metadata {
+            name: Some("Sword".to_string()),
+            description: Some("A sharp sword".to_string()),
+            image: Some("ipfs://sword".to_string()),
+            attributes: Some(vec![Trait {
+                display_type: None,
+                trait_type: "damage".to_string(),
+                value: "10".to_string(),
+            }]),
+            ..Metadata::default()
         };
 
-        // USER_1 approves marketplace to spend nft
+        // the standard collection does not accept metadata
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
-            Addr::unchecked("contract3".to_string()),
-            &cw721::Cw721ExecuteMsg::Approve {
-                spender: marketplace_address.clone(),
-                token_id: "1".to_string(),
-                expires: None,
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: Some(metadata.clone()),
             },
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // USER_1 lists nft for sale
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address),
-            &list_nft_msg,
+            &ExecuteMsg::MintNft {
+                contract_address: "contract4".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: Some(metadata.clone()),
+            },
             &[],
         );
         assert!(res.is_ok());
-    }
 
+        // the traits can be read on-chain
+        let nft_info: cw721::NftInfoResponse<Option<Metadata>> = app
+            .wrap()
+            .query_wasm_smart(
+                "contract4",
+                &cw721::Cw721QueryMsg::NftInfo {
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(nft_info.extension, Some(metadata));
+    }
     #[test]
-    fn user_can_listing_and_buying_nft() {
+    fn creator_can_batch_mint_to_recipients() {
         // get integration test app and contracts
         let (mut app, contracts) = instantiate_contracts();
-        // let cw721_address = contracts[0].contract_addr.clone();
         let marketplace_address = contracts[1].contract_addr.clone();
 
-        // prepare create collection message
-        let create_collection_msg = ExecuteMsg::CreateCollection {
-            name: "NFT_A".to_string(),
-            symbol: "NFT".to_string(),
-        };
-
         // USER_1 creates collection
-        let res = app.execute_contract(
+        app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &create_collection_msg,
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
             &[],
-        );
-        assert!(res.is_ok());
+        )
+        .unwrap();
 
-        // prepare mint token message
-        let mint_token_msg = ExecuteMsg::MintNft {
-            contract_address: "contract3".to_string(),
-            token_id: "1".to_string(),
+        let mint_item = |token_id: usize, owner: &str| MintItem {
+            token_id: Some(token_id.to_string()),
             token_uri: "https://www.google.com".to_string(),
+            owner: owner.to_string(),
+            extension: None,
         };
 
-        // USER_1 mints token
+        // the batch size is bounded
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &mint_token_msg,
+            &ExecuteMsg::BatchMint {
+                contract_address: "contract3".to_string(),
+                tokens: (1..=51)
+                    .map(|token_id| mint_item(token_id, USER_2))
+                    .collect(),
+            },
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // prepare list nft message
-        let list_nft_msg = ExecuteMsg::ListNft {
-            asset: NftAsset {
-                contract_address: Addr::unchecked("contract3".to_string()),
-                token_id: Some("1".to_string()),
-            },
-            listing_config: AuctionConfig::FixedPrice {
-                price: PaymentAsset::Native {
-                    denom: NATIVE_DENOM.to_string(),
-                    amount: 100u128.into(),
-                },
-                start_time: None,
-                end_time: None,
-            },
+        // USER_2 is not the creator of the collection
+        let batch_mint_msg = ExecuteMsg::BatchMint {
+            contract_address: "contract3".to_string(),
+            tokens: vec![mint_item(1, USER_2), mint_item(2, OWNER)],
         };
-
-        // USER_1 approves marketplace to spend nft
         let res = app.execute_contract(
-            Addr::unchecked(USER_1.to_string()),
-            Addr::unchecked("contract3".to_string()),
-            &cw721::Cw721ExecuteMsg::Approve {
-                spender: marketplace_address.clone(),
-                token_id: "1".to_string(),
-                expires: None,
-            },
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &batch_mint_msg,
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // USER_1 lists nft for sale
+        // USER_1 airdrops token 1 to USER_2 and token 2 to OWNER
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
-            Addr::unchecked(marketplace_address.clone()),
-            &list_nft_msg,
+            Addr::unchecked(marketplace_address),
+            &batch_mint_msg,
             &[],
         );
         assert!(res.is_ok());
 
-        // prepare buy nft message
-        let buy_nft_msg = ExecuteMsg::Buy {
-            asset: NftAsset {
-                contract_address: Addr::unchecked("contract3".to_string()),
-                token_id: Some("1".to_string()),
+        for (token_id, owner) in [("1", USER_2), ("2", OWNER)] {
+            let res: cw721::OwnerOfResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    "contract3",
+                    &cw721::Cw721QueryMsg::OwnerOf {
+                        token_id: token_id.to_string(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.owner, owner);
+        }
+    }
+
+    #[test]
+    fn failed_collection_instantiation_is_dropped() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // OWNER adds a template with a code id which does not exist
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::SetCollectionTemplate {
+                name: "broken".to_string(),
+                code_id: 999,
+                kind: CollectionKind::Standard,
             },
-        };
+            &[],
+        )
+        .unwrap();
 
-        // Query balance of USER_1
-        let req: QueryRequest<BankQuery> = QueryRequest::Bank(BankQuery::Balance {
-            address: USER_1.to_string(),
-            denom: NATIVE_DENOM.to_string(),
-        });
         let res = app
-            .wrap()
-            .raw_query(&to_json_binary(&req).unwrap())
-            .unwrap()
+            .execute_contract(
+                Addr::unchecked(USER_1.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::CreateCollection {
+                    name: "NFT_A".to_string(),
+                    symbol: "NFT".to_string(),
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    max_supply: None,
+                    royalty_bps: None,
+                    royalty_recipient: None,
+                    token_id_mode: None,
+                    template: Some("broken".to_string()),
+                },
+                &[],
+            )
             .unwrap();
-        let balance_before: BankBalanceResponse = from_json(&res).unwrap();
-
-        // USER_2 buys nft
-        let res = app.execute_contract(
-            Addr::unchecked(USER_2.to_string()),
-            Addr::unchecked(marketplace_address),
-            &buy_nft_msg,
-            &[Coin {
-                denom: NATIVE_DENOM.to_string(),
-                amount: 100u128.into(),
-            }],
-        );
-        assert!(res.is_ok());
 
-        // Query balance of USER_1
-        let req: QueryRequest<BankQuery> = QueryRequest::Bank(BankQuery::Balance {
-            address: USER_1.to_string(),
-            denom: NATIVE_DENOM.to_string(),
-        });
-        let res = app
+        // the failure is reported and no collection is saved
+        let event = res
+            .events
+            .iter()
+            .find(|ev| ev.ty == "wasm-marketplace-create-collection-failed")
+            .unwrap();
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "creator" && attr.value == USER_1));
+        let res: CollectionsResponse = app
             .wrap()
-            .raw_query(&to_json_binary(&req).unwrap())
-            .unwrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::Collections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
             .unwrap();
-        let balance_after: BankBalanceResponse = from_json(&res).unwrap();
-        assert_eq!(
-            balance_after
-                .amount
-                .amount
-                .checked_sub(Uint128::from(100u128))
-                .unwrap(),
-            balance_before.amount.amount
-        );
+        assert!(res.collections.is_empty());
     }
 
     #[test]
-    fn user_can_listing_and_buying_using_cw20_token() {
+    fn soulbound_tokens_cannot_be_listed() {
         // get integration test app and contracts
         let (mut app, contracts) = instantiate_contracts();
-        let cw20_address = contracts[2].contract_addr.clone();
         let marketplace_address = contracts[1].contract_addr.clone();
 
-        // prepare create collection message
-        let create_collection_msg = ExecuteMsg::CreateCollection {
-            name: "NFT_A".to_string(),
-            symbol: "NFT".to_string(),
-        };
-
-        // USER_1 creates collection
-        let res = app.execute_contract(
-            Addr::unchecked(USER_1.to_string()),
+        // OWNER adds a soulbound template, the standard cw721 code stands for the soulbound code
+        let res: CollectionTemplatesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::CollectionTemplates {},
+            )
+            .unwrap();
+        let standard = res
+            .templates
+            .iter()
+            .find(|template| template.kind == CollectionKind::Standard)
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &create_collection_msg,
+            &ExecuteMsg::SetCollectionTemplate {
+                name: "badges".to_string(),
+                code_id: standard.code_id,
+                kind: CollectionKind::Soulbound,
+            },
             &[],
-        );
-        assert!(res.is_ok());
+        )
+        .unwrap();
 
-        // prepare mint token message
-        let mint_token_msg = ExecuteMsg::MintNft {
-            contract_address: "contract3".to_string(),
-            token_id: "1".to_string(),
-            token_uri: "https://www.google.com".to_string(),
+        // an unknown template cannot be used
+        let create_collection_msg = |template: &str| ExecuteMsg::CreateCollection {
+            name: "TICKETS".to_string(),
+            symbol: "TKT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: Some(template.to_string()),
         };
-
-        // USER_1 mints token
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &mint_token_msg,
+            &create_collection_msg("editions"),
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // prepare list nft message
-        let list_nft_msg = ExecuteMsg::ListNft {
-            asset: NftAsset {
-                contract_address: Addr::unchecked("contract3".to_string()),
+        // USER_1 creates a soulbound collection, mints and approves token 1
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &create_collection_msg("badges"),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
                 token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
             },
-            listing_config: AuctionConfig::FixedPrice {
-                price: PaymentAsset::Cw20 {
-                    contract_address: Addr::unchecked(cw20_address.clone()),
-                    amount: 100u128,
-                },
-                start_time: None,
-                end_time: None,
-            },
-        };
-
-        // USER_1 approves marketplace to spend nft
-        let res = app.execute_contract(
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked("contract3".to_string()),
             &cw721::Cw721ExecuteMsg::Approve {
@@ -369,108 +590,2449 @@ mod listing_nft {
                 expires: None,
             },
             &[],
-        );
-        assert!(res.is_ok());
+        )
+        .unwrap();
 
-        // USER_1 lists nft for sale
+        // the token cannot be listed
         let res = app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
-            Addr::unchecked(marketplace_address.clone()),
-            &list_nft_msg,
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::ListNft {
+                asset: NftAsset {
+                    contract_address: Addr::unchecked("contract3".to_string()),
+                    token_id: Some("1".to_string()),
+                },
+                listing_config: AuctionConfig::FixedPrice {
+                    price: PaymentAsset::Native {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: 100u128,
+                    },
+                    start_time: None,
+                    end_time: None,
+                    merkle_root: None,
+                },
+            },
             &[],
         );
         assert!(res.is_err());
+    }
 
-        // OWNER allows cw20 token to be used for payment
-        let res = app.execute_contract(
+    #[test]
+    fn creator_can_migrate_own_collection() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+        let upgraded_code_id = app.store_code(cw721_upgraded_contract_template());
+
+        // OWNER adds a template for the upgraded code id
+        app.execute_contract(
             Addr::unchecked(OWNER.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &ExecuteMsg::AllowPaymentToken {
-                contract_address: Addr::unchecked(cw20_address.clone()),
+            &ExecuteMsg::SetCollectionTemplate {
+                name: "upgraded".to_string(),
+                code_id: upgraded_code_id,
+                kind: CollectionKind::OnchainMetadata,
             },
             &[],
-        );
-        assert!(res.is_ok());
+        )
+        .unwrap();
 
-        // USER_1 lists nft for sale
-        let res = app.execute_contract(
+        // USER_1 creates collection
+        app.execute_contract(
             Addr::unchecked(USER_1.to_string()),
             Addr::unchecked(marketplace_address.clone()),
-            &list_nft_msg,
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let migrate_msg = ExecuteMsg::MigrateCollections {
+            code_id: upgraded_code_id,
+            collections: vec!["contract3".to_string()],
+            msg: to_json_binary(&Empty {}).unwrap(),
+        };
+
+        // USER_2 is neither the owner nor the creator
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &migrate_msg,
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // prepare buy nft message
-        let buy_nft_msg = ExecuteMsg::Buy {
-            asset: NftAsset {
-                contract_address: Addr::unchecked("contract3".to_string()),
-                token_id: Some("1".to_string()),
-            },
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &migrate_msg,
+            &[],
+        )
+        .unwrap();
+
+        let contract_info = app.wrap().query_wasm_contract_info("contract3").unwrap();
+        assert_eq!(contract_info.code_id, upgraded_code_id);
+        let res: CollectionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::Collection {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collection.code_id, Some(upgraded_code_id));
+        assert_eq!(res.collection.kind, CollectionKind::OnchainMetadata);
+        assert_eq!(res.collection.code_version, Some("0.19.0".to_string()));
+    }
+
+    #[test]
+    fn soulbound_collections_cannot_change_kind() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+        let soulbound_code_id = app.store_code(cw721_upgraded_contract_template());
+        let standard_code_id = app.store_code(cw721_upgraded_contract_template());
+        let unknown_code_id = app.store_code(cw721_upgraded_contract_template());
+
+        // OWNER adds a soulbound and a standard template
+        for (name, code_id, kind) in [
+            ("soulbound", soulbound_code_id, CollectionKind::Soulbound),
+            ("standard-v2", standard_code_id, CollectionKind::Standard),
+        ] {
+            app.execute_contract(
+                Addr::unchecked(OWNER.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::SetCollectionTemplate {
+                    name: name.to_string(),
+                    code_id,
+                    kind,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // USER_1 creates a soulbound collection and a standard collection
+        for template in ["soulbound", "standard"] {
+            app.execute_contract(
+                Addr::unchecked(USER_1.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::CreateCollection {
+                    name: "NFT_A".to_string(),
+                    symbol: "NFT".to_string(),
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    max_supply: None,
+                    royalty_bps: None,
+                    royalty_recipient: None,
+                    token_id_mode: None,
+                    template: Some(template.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let migrate = |app: &mut cw_multi_test::App, collection: &str, code_id: u64| {
+            app.execute_contract(
+                Addr::unchecked(USER_1.to_string()),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MigrateCollections {
+                    code_id,
+                    collections: vec![collection.to_string()],
+                    msg: to_json_binary(&Empty {}).unwrap(),
+                },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())
         };
 
-        // Query balance cw20 token of USER_2
-        let balance_before: BalanceResponse = app
+        // the soulbound collection cannot become standard, even through a code id without template
+        assert!(matches!(
+            migrate(&mut app, "contract3", standard_code_id),
+            Err(ContractError::SoulboundKindChange { .. })
+        ));
+        assert!(matches!(
+            migrate(&mut app, "contract3", unknown_code_id),
+            Err(ContractError::SoulboundKindChange { .. })
+        ));
+        // the standard collection cannot become soulbound
+        assert!(matches!(
+            migrate(&mut app, "contract4", soulbound_code_id),
+            Err(ContractError::SoulboundKindChange { .. })
+        ));
+
+        // both collections can be migrated within their kind
+        migrate(&mut app, "contract3", soulbound_code_id).unwrap();
+        migrate(&mut app, "contract4", standard_code_id).unwrap();
+        let res: CollectionResponse = app
             .wrap()
             .query_wasm_smart(
-                cw20_address.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: USER_2.to_string(),
+                marketplace_address,
+                &QueryMsg::Collection {
+                    contract_address: "contract3".to_string(),
                 },
             )
             .unwrap();
+        assert_eq!(res.collection.kind, CollectionKind::Soulbound);
+        assert_eq!(res.collection.code_id, Some(soulbound_code_id));
+    }
 
-        // USER_2 allows marketplace to spend cw20 token
+    #[test]
+    fn creator_can_add_minters_and_transfer_collection() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, with a royalty paid to USER_1
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: Some(500),
+                royalty_recipient: None,
+                token_id_mode: Some(TokenIdMode::Sequential),
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let mint_token_msg = ExecuteMsg::MintNft {
+            contract_address: "contract3".to_string(),
+            token_id: None,
+            token_uri: "https://www.google.com".to_string(),
+            extension: None,
+        };
+
+        // USER_2 cannot mint before being added as minter
         let res = app.execute_contract(
             Addr::unchecked(USER_2.to_string()),
-            Addr::unchecked(cw20_address.clone()),
-            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
-                spender: marketplace_address.clone(),
-                amount: 100u128.into(),
-                expires: None,
-            },
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
 
-        // USER_2 buys nft
+        // only the creator can add minters
+        let add_minter_msg = ExecuteMsg::AddMinter {
+            contract_address: "contract3".to_string(),
+            minter: USER_2.to_string(),
+        };
         let res = app.execute_contract(
             Addr::unchecked(USER_2.to_string()),
-            Addr::unchecked(marketplace_address),
-            &buy_nft_msg,
+            Addr::unchecked(marketplace_address.clone()),
+            &add_minter_msg,
             &[],
         );
-        assert!(res.is_ok());
+        assert!(res.is_err());
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &add_minter_msg,
+            &[],
+        )
+        .unwrap();
 
-        // Query balance cw20 token of USER_2
-        let balance_after: BalanceResponse = app
+        let res: CollectionMintersResponse = app
             .wrap()
             .query_wasm_smart(
-                cw20_address.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: USER_2.to_string(),
+                marketplace_address.clone(),
+                &QueryMsg::CollectionMinters {
+                    contract_address: "contract3".to_string(),
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap();
-        assert_eq!(
-            balance_after.balance,
-            balance_before
-                .balance
-                .checked_sub(Uint128::from(100u128))
-                .unwrap()
+        assert_eq!(res.minters, vec![Addr::unchecked(USER_2)]);
+
+        app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
+            &[],
+        )
+        .unwrap();
+
+        // the owner recovers the collection for OWNER
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::TransferCollectionCreator {
+                contract_address: "contract3".to_string(),
+                new_creator: OWNER.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // USER_1 is no longer the creator
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
+            &[],
         );
+        assert!(res.is_err());
 
-        // Query balance cw20 token of USER_1
-        let balance: BalanceResponse = app
+        let res: CollectionsResponse = app
             .wrap()
             .query_wasm_smart(
-                cw20_address.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: USER_1.to_string(),
+                marketplace_address.clone(),
+                &QueryMsg::CollectionsByCreator {
+                    creator: OWNER.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collections.len(), 1);
+        // the royalty follows the creator
+        assert_eq!(
+            res.collections[0]
+                .collection
+                .royalty
+                .as_ref()
+                .map(|royalty| royalty.recipient.clone()),
+            Some(Addr::unchecked(OWNER))
+        );
+        let res: CollectionsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::CollectionsByCreator {
+                    creator: USER_1.to_string(),
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap();
+        assert!(res.collections.is_empty());
 
-        assert_eq!(balance.balance, Uint128::from(100u128));
+        // the new creator removes USER_2
+        app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::RemoveMinter {
+                contract_address: "contract3".to_string(),
+                minter: USER_2.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address),
+            &mint_token_msg,
+            &[],
+        );
+        assert!(res.is_err());
+    }
+}
+
+mod listing_nft {
+    use cosmwasm_std::{Coin, Uint128};
+    use cw20::BalanceResponse;
+
+    use crate::error::ContractError;
+
+    use crate::test_setup::env::OWNER;
+
+    use super::*;
+
+    #[test]
+    fn user_can_list_nft_for_sale() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        // let cw721_address = contracts[0].contract_addr.clone();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // prepare create collection message
+        let create_collection_msg = ExecuteMsg::CreateCollection {
+            name: "NFT_A".to_string(),
+            symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: None,
+        };
+
+        // USER_1 creates collection
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &create_collection_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare mint token message
+        let mint_token_msg = ExecuteMsg::MintNft {
+            contract_address: "contract3".to_string(),
+            token_id: Some("1".to_string()),
+            token_uri: "https://www.google.com".to_string(),
+            extension: None,
+        };
+
+        // USER_1 mints token
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare list nft message
+        let list_nft_msg = ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3".to_string()),
+                token_id: Some("1".to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: 100u128,
+                },
+                start_time: None,
+                end_time: None,
+                merkle_root: None,
+            },
+        };
+
+        // USER_1 approves marketplace to spend nft
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked("contract3".to_string()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_1 lists nft for sale
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address),
+            &list_nft_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn seller_can_update_listing_in_place() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, mints token 1 and lists it for 100
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            100,
+            None,
+        );
+        let listed_height = app.block_info().height;
+
+        let asset = NftAsset {
+            contract_address: Addr::unchecked("contract3"),
+            token_id: Some("1".to_string()),
+        };
+        let end_time = cw721::Expiration::AtHeight(listed_height + 10);
+        let update_listing_msg = |end_time: cw721::Expiration| ExecuteMsg::UpdateListing {
+            asset: asset.clone(),
+            price: Some(PaymentAsset::Native {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 50,
+            }),
+            end_time: Some(end_time),
+        };
+
+        // only the seller can update the listing
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &update_listing_msg(end_time),
+            &[],
+        );
+        assert!(res.is_err());
+
+        // the end_time cannot be in the past
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &update_listing_msg(cw721::Expiration::AtHeight(listed_height)),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::EndTimeExpired { .. }
+        ));
+
+        app.update_block(|block| block.height += 1);
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &update_listing_msg(end_time),
+                &[],
+            )
+            .unwrap();
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "action" && attr.value == "update_listing")));
+
+        // the listing keeps its height, the price and the end_time are replaced
+        let res: crate::structs::ListingResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &crate::msg::QueryMsg::Listing {
+                    contract_address: Addr::unchecked("contract3"),
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.listing.price_key(), (NATIVE_DENOM.to_string(), 50));
+        assert_eq!(res.listing.end_time, Some(end_time));
+        assert_eq!(res.listing.height, listed_height);
+        assert_eq!(res.listing.version, 1);
+
+        let res: crate::structs::CollectionStatsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &crate::msg::QueryMsg::CollectionStats {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.stats[0].stats.stored_listing_count, 1);
+        assert_eq!(res.stats[0].floor_price, Some(50));
+    }
+
+    #[test]
+    fn listing_requires_a_never_expiring_approval() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection and mints token 1
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let list = |app: &mut cw_multi_test::App| -> ContractError {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::ListNft {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked("contract3"),
+                        token_id: Some("1".to_string()),
+                    },
+                    listing_config: AuctionConfig::FixedPrice {
+                        price: PaymentAsset::Native {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: 100,
+                        },
+                        start_time: None,
+                        end_time: None,
+                        merkle_root: None,
+                    },
+                },
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap()
+        };
+
+        assert!(matches!(list(&mut app), ContractError::ApprovalRequired {}));
+
+        // an approval which expires is not enough
+        let expires = cw721::Expiration::AtHeight(app.block_info().height + 100);
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked("contract3"),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: Some(expires),
+            },
+            &[],
+        )
+        .unwrap();
+        assert!(matches!(
+            list(&mut app),
+            ContractError::ApprovalExpiring { expires: approval_expires }
+                if approval_expires == expires
+        ));
+    }
+
+    #[test]
+    fn failed_buys_return_typed_errors() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, mints token 1 and lists it until the height + 10
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let expires_at = app.block_info().height + 10;
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            100,
+            Some(cw721::Expiration::AtHeight(expires_at)),
+        );
+
+        let buy = |app: &mut cw_multi_test::App, buyer: &str, token_id: Option<&str>| {
+            app.execute_contract(
+                Addr::unchecked(buyer),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::Buy {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked("contract3"),
+                        token_id: token_id.map(|token_id| token_id.to_string()),
+                    },
+                    proof: None,
+                },
+                &[Coin::new(100, NATIVE_DENOM)],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap()
+        };
+
+        assert!(matches!(
+            buy(&mut app, USER_2, None),
+            ContractError::TokenIdRequired {}
+        ));
+        assert!(matches!(
+            buy(&mut app, USER_1, Some("1")),
+            ContractError::CannotBuyOwnListing {}
+        ));
+
+        app.update_block(|block| block.height = expires_at);
+        assert!(matches!(
+            buy(&mut app, USER_2, Some("1")),
+            ContractError::ListingExpired { end_time }
+                if end_time == cw721::Expiration::AtHeight(expires_at)
+        ));
+    }
+
+    #[test]
+    fn sale_emits_marketplace_event() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection with a royalty of 5%, mints token 1 and lists it for 1000
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: Some(500),
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            1000,
+            None,
+        );
+
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER_2),
+                Addr::unchecked(marketplace_address),
+                &ExecuteMsg::Buy {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked("contract3"),
+                        token_id: Some("1".to_string()),
+                    },
+                    proof: None,
+                },
+                &[Coin::new(1000, NATIVE_DENOM)],
+            )
+            .unwrap();
+
+        let event = res
+            .events
+            .iter()
+            .find(|event| event.ty == "wasm-marketplace-sale")
+            .unwrap();
+        let attribute = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+                .unwrap()
+        };
+        assert_eq!(attribute("schema_version"), "1");
+        assert_eq!(attribute("contract_address"), "contract3");
+        assert_eq!(attribute("token_id"), "1");
+        assert_eq!(attribute("seller"), USER_1);
+        assert_eq!(attribute("buyer"), USER_2);
+        assert_eq!(attribute("price"), "1000");
+        assert_eq!(attribute("denom"), NATIVE_DENOM);
+        assert_eq!(attribute("royalty"), "50");
+        assert_eq!(attribute("start_time_kind"), "none");
+        assert_eq!(attribute("end_time_kind"), "none");
+        assert_eq!(attribute("end_time"), "none");
+    }
+
+    #[test]
+    fn relisting_emits_replace_listing_event() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, mints token 1 and lists it until the height + 10
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let expires_at = app.block_info().height + 10;
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            100,
+            Some(cw721::Expiration::AtHeight(expires_at)),
+        );
+
+        // USER_1 lists the token again at another price
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address),
+                &ExecuteMsg::ListNft {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked("contract3"),
+                        token_id: Some("1".to_string()),
+                    },
+                    listing_config: AuctionConfig::FixedPrice {
+                        price: PaymentAsset::Native {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: 200,
+                        },
+                        start_time: None,
+                        end_time: None,
+                        merkle_root: None,
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+
+        // the replaced listing is reported before the new one
+        let types = res
+            .events
+            .iter()
+            .map(|event| event.ty.as_str())
+            .filter(|ty| ty.starts_with("wasm-marketplace-"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            ["wasm-marketplace-replace-listing", "wasm-marketplace-list"]
+        );
+        let replaced = &res
+            .events
+            .iter()
+            .find(|event| event.ty == "wasm-marketplace-replace-listing")
+            .unwrap()
+            .attributes;
+        let attribute = |key: &str| {
+            replaced
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+                .unwrap()
+        };
+        assert_eq!(attribute("price"), "100");
+        assert_eq!(attribute("end_time_kind"), "height");
+        assert_eq!(attribute("end_time"), expires_at.to_string());
+        assert_eq!(attribute("replaced_by"), USER_1);
+    }
+
+    #[test]
+    fn user_can_listing_and_buying_nft() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        // let cw721_address = contracts[0].contract_addr.clone();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // prepare create collection message
+        let create_collection_msg = ExecuteMsg::CreateCollection {
+            name: "NFT_A".to_string(),
+            symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: None,
+        };
+
+        // USER_1 creates collection
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &create_collection_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare mint token message
+        let mint_token_msg = ExecuteMsg::MintNft {
+            contract_address: "contract3".to_string(),
+            token_id: Some("1".to_string()),
+            token_uri: "https://www.google.com".to_string(),
+            extension: None,
+        };
+
+        // USER_1 mints token
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare list nft message
+        let list_nft_msg = ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3".to_string()),
+                token_id: Some("1".to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: 100u128,
+                },
+                start_time: None,
+                end_time: None,
+                merkle_root: None,
+            },
+        };
+
+        // USER_1 approves marketplace to spend nft
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked("contract3".to_string()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_1 lists nft for sale
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_nft_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare buy nft message
+        let buy_nft_msg = ExecuteMsg::Buy {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3".to_string()),
+                token_id: Some("1".to_string()),
+            },
+            proof: None,
+        };
+
+        // Query balance of USER_1
+        let req: QueryRequest<BankQuery> = QueryRequest::Bank(BankQuery::Balance {
+            address: USER_1.to_string(),
+            denom: NATIVE_DENOM.to_string(),
+        });
+        let res = app
+            .wrap()
+            .raw_query(&to_json_binary(&req).unwrap())
+            .unwrap()
+            .unwrap();
+        let balance_before: BankBalanceResponse = from_json(&res).unwrap();
+
+        // USER_2 buys nft
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address),
+            &buy_nft_msg,
+            &[Coin {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 100u128.into(),
+            }],
+        );
+        assert!(res.is_ok());
+
+        // Query balance of USER_1
+        let req: QueryRequest<BankQuery> = QueryRequest::Bank(BankQuery::Balance {
+            address: USER_1.to_string(),
+            denom: NATIVE_DENOM.to_string(),
+        });
+        let res = app
+            .wrap()
+            .raw_query(&to_json_binary(&req).unwrap())
+            .unwrap()
+            .unwrap();
+        let balance_after: BankBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(
+            balance_after
+                .amount
+                .amount
+                .checked_sub(Uint128::from(100u128))
+                .unwrap(),
+            balance_before.amount.amount
+        );
+    }
+
+    #[test]
+    fn user_can_listing_and_buying_using_cw20_token() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let cw20_address = contracts[2].contract_addr.clone();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // prepare create collection message
+        let create_collection_msg = ExecuteMsg::CreateCollection {
+            name: "NFT_A".to_string(),
+            symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            token_id_mode: None,
+            template: None,
+        };
+
+        // USER_1 creates collection
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &create_collection_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare mint token message
+        let mint_token_msg = ExecuteMsg::MintNft {
+            contract_address: "contract3".to_string(),
+            token_id: Some("1".to_string()),
+            token_uri: "https://www.google.com".to_string(),
+            extension: None,
+        };
+
+        // USER_1 mints token
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &mint_token_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare list nft message
+        let list_nft_msg = ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3".to_string()),
+                token_id: Some("1".to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Cw20 {
+                    contract_address: Addr::unchecked(cw20_address.clone()),
+                    amount: 100u128,
+                },
+                start_time: None,
+                end_time: None,
+                merkle_root: None,
+            },
+        };
+
+        // USER_1 approves marketplace to spend nft
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked("contract3".to_string()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_1 lists nft for sale
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_nft_msg,
+            &[],
+        );
+        assert!(res.is_err());
+
+        // OWNER allows cw20 token to be used for payment
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::AllowPaymentToken {
+                contract_address: Addr::unchecked(cw20_address.clone()),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_1 lists nft for sale
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_nft_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // prepare buy nft message
+        let buy_nft_msg = ExecuteMsg::Buy {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3".to_string()),
+                token_id: Some("1".to_string()),
+            },
+            proof: None,
+        };
+
+        // Query balance cw20 token of USER_2
+        let balance_before: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_address.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: USER_2.to_string(),
+                },
+            )
+            .unwrap();
+
+        // USER_2 allows marketplace to spend cw20 token
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(cw20_address.clone()),
+            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: marketplace_address.clone(),
+                amount: 100u128.into(),
+                expires: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // USER_2 buys nft
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address),
+            &buy_nft_msg,
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // Query balance cw20 token of USER_2
+        let balance_after: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_address.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: USER_2.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            balance_after.balance,
+            balance_before
+                .balance
+                .checked_sub(Uint128::from(100u128))
+                .unwrap()
+        );
+
+        // Query balance cw20 token of USER_1
+        let balance: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_address.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: USER_1.to_string(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(balance.balance, Uint128::from(100u128));
+    }
+}
+
+mod royalties {
+    use cosmwasm_std::{Coin, Uint128};
+
+    use crate::error::ContractError;
+    use crate::msg::QueryMsg;
+    use crate::structs::{CollectionResponse, Royalty, MAX_ROYALTY_BPS};
+    use crate::test_setup::env::{native_balance, OWNER};
+
+    use super::*;
+
+    #[test]
+    fn royalty_is_capped_and_zero_bps_means_no_royalty() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        let create_collection_msg = |royalty_bps: u16| ExecuteMsg::CreateCollection {
+            name: "NFT_A".to_string(),
+            symbol: "NFT".to_string(),
+            description: None,
+            image: None,
+            external_link: None,
+            max_supply: None,
+            royalty_bps: Some(royalty_bps),
+            royalty_recipient: Some(OWNER.to_string()),
+            token_id_mode: None,
+            template: None,
+        };
+
+        // the royalty cannot exceed the maximum
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &create_collection_msg(MAX_ROYALTY_BPS + 1),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::RoyaltyTooHigh { bps, max }
+                if bps == MAX_ROYALTY_BPS + 1 && max == MAX_ROYALTY_BPS
+        ));
+
+        // a royalty of 0 bps is no royalty, even with a recipient
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &create_collection_msg(0),
+            &[],
+        )
+        .unwrap();
+        let res: CollectionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address,
+                &QueryMsg::Collection {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collection.royalty, None);
+    }
+
+    #[test]
+    fn royalty_is_paid_on_sale_of_created_collection() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection with 10% royalty paid to OWNER
+        let res = app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: Some("The collection A".to_string()),
+                image: Some("ipfs://collection-a".to_string()),
+                external_link: None,
+                max_supply: Some(100),
+                royalty_bps: Some(1_000),
+                royalty_recipient: Some(OWNER.to_string()),
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let res: CollectionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::Collection {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collection.max_supply, Some(100));
+        assert_eq!(
+            res.collection.royalty,
+            Some(Royalty {
+                bps: 1_000,
+                recipient: Addr::unchecked(OWNER),
+            })
+        );
+
+        // USER_1 mints, approves and lists token 1 for 100
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked("contract3".to_string()),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.clone(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let asset = NftAsset {
+            contract_address: Addr::unchecked("contract3".to_string()),
+            token_id: Some("1".to_string()),
+        };
+        app.execute_contract(
+            Addr::unchecked(USER_1.to_string()),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::ListNft {
+                asset: asset.clone(),
+                listing_config: AuctionConfig::FixedPrice {
+                    price: PaymentAsset::Native {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: 100u128,
+                    },
+                    start_time: None,
+                    end_time: None,
+                    merkle_root: None,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let seller_balance_before = native_balance(&app, USER_1);
+        let royalty_balance_before = native_balance(&app, OWNER);
+
+        // USER_2 buys token 1
+        let res = app.execute_contract(
+            Addr::unchecked(USER_2.to_string()),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::Buy { asset, proof: None },
+            &[Coin {
+                denom: NATIVE_DENOM.to_string(),
+                amount: 100u128.into(),
+            }],
+        );
+        assert!(res.is_ok());
+
+        // the seller receives 90 and the royalty recipient receives 10
+        assert_eq!(
+            native_balance(&app, USER_1),
+            seller_balance_before + Uint128::from(90u128)
+        );
+        assert_eq!(
+            native_balance(&app, OWNER),
+            royalty_balance_before + Uint128::from(10u128)
+        );
+    }
+}
+
+mod sales_history {
+    use cosmwasm_std::Coin;
+
+    use crate::msg::QueryMsg;
+    use crate::structs::{CollectionStatsResponse, SalesFilter, SalesResponse};
+
+    use super::*;
+
+    // approve, list and sell the token for the price
+    fn sell(
+        app: &mut cw_multi_test::App,
+        marketplace_address: &str,
+        seller: &str,
+        buyer: &str,
+        price: u128,
+    ) {
+        app.execute_contract(
+            Addr::unchecked(seller),
+            Addr::unchecked("contract3"),
+            &cw721::Cw721ExecuteMsg::Approve {
+                spender: marketplace_address.to_string(),
+                token_id: "1".to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let asset = NftAsset {
+            contract_address: Addr::unchecked("contract3"),
+            token_id: Some("1".to_string()),
+        };
+        app.execute_contract(
+            Addr::unchecked(seller),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::ListNft {
+                asset: asset.clone(),
+                listing_config: AuctionConfig::FixedPrice {
+                    price: PaymentAsset::Native {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: price,
+                    },
+                    start_time: None,
+                    end_time: None,
+                    merkle_root: None,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(buyer),
+            Addr::unchecked(marketplace_address),
+            &ExecuteMsg::Buy { asset, proof: None },
+            &[Coin {
+                denom: NATIVE_DENOM.to_string(),
+                amount: price.into(),
+            }],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn sales_are_recorded_with_most_recent_first() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection and mints token 1
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::MintNft {
+                contract_address: "contract3".to_string(),
+                token_id: Some("1".to_string()),
+                token_uri: "https://www.google.com".to_string(),
+                extension: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // token 1 is sold to USER_2 for 100, then back to USER_1 for 150
+        sell(&mut app, &marketplace_address, USER_1, USER_2, 100);
+        app.update_block(|block| block.height += 1);
+        sell(&mut app, &marketplace_address, USER_2, USER_1, 150);
+
+        let sales_history = |filter: SalesFilter, start_after: Option<u64>| -> SalesResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::SalesHistory {
+                        filter,
+                        start_after,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+        };
+
+        // the provenance of token 1, the last sale first
+        let res = sales_history(
+            SalesFilter::Token {
+                contract_address: "contract3".to_string(),
+                token_id: "1".to_string(),
+            },
+            None,
+        );
+        assert_eq!(res.sales.len(), 2);
+        assert_eq!(res.sales[0].sale_id, 2);
+        assert_eq!(res.sales[0].price.amount(), 150);
+        assert_eq!(res.sales[0].seller, Addr::unchecked(USER_2));
+        assert_eq!(res.sales[0].buyer, Addr::unchecked(USER_1));
+        assert_eq!(res.sales[0].height, res.sales[1].height + 1);
+
+        // the next page starts after sale 2
+        let res = sales_history(SalesFilter::All {}, Some(2));
+        assert_eq!(res.sales.len(), 1);
+        assert_eq!(res.sales[0].sale_id, 1);
+
+        let res = sales_history(
+            SalesFilter::Buyer {
+                address: USER_2.to_string(),
+            },
+            None,
+        );
+        assert_eq!(res.sales.len(), 1);
+        assert_eq!(res.sales[0].price.amount(), 100);
+
+        let res = sales_history(
+            SalesFilter::Seller {
+                address: USER_2.to_string(),
+            },
+            None,
+        );
+        assert_eq!(res.sales.len(), 1);
+        assert_eq!(res.sales[0].sale_id, 2);
+
+        let res = sales_history(
+            SalesFilter::Collection {
+                contract_address: "contract3".to_string(),
+            },
+            None,
+        );
+        assert_eq!(res.sales.len(), 2);
+    }
+
+    #[test]
+    fn collection_stats_track_sales_and_floor() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection and mints tokens 1, 2 and 3
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["1", "2", "3"] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: "contract3".to_string(),
+                    token_id: Some(token_id.to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // token 1 is sold for 100, tokens 2 and 3 are listed for 300 and 200
+        sell(&mut app, &marketplace_address, USER_1, USER_2, 100);
+        for (token_id, price) in [("2", 300u128), ("3", 200u128)] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked("contract3"),
+                &cw721::Cw721ExecuteMsg::Approve {
+                    spender: marketplace_address.clone(),
+                    token_id: token_id.to_string(),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::ListNft {
+                    asset: NftAsset {
+                        contract_address: Addr::unchecked("contract3"),
+                        token_id: Some(token_id.to_string()),
+                    },
+                    listing_config: AuctionConfig::FixedPrice {
+                        price: PaymentAsset::Native {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: price,
+                        },
+                        start_time: None,
+                        end_time: None,
+                        merkle_root: None,
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let collection_stats = |app: &cw_multi_test::App| -> CollectionStatsResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::CollectionStats {
+                        contract_address: "contract3".to_string(),
+                    },
+                )
+                .unwrap()
+        };
+
+        let res = collection_stats(&app);
+        assert_eq!(res.stats.len(), 1);
+        assert_eq!(res.stats[0].denom, NATIVE_DENOM);
+        assert_eq!(res.stats[0].stats.volume, 100);
+        assert_eq!(res.stats[0].stats.sales_count, 1);
+        assert_eq!(res.stats[0].stats.last_sale_price, Some(100));
+        assert_eq!(res.stats[0].stats.stored_listing_count, 2);
+        assert_eq!(res.stats[0].floor_price, Some(200));
+
+        // the floor moves up when the cheapest listing is cancelled
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Cancel {
+                asset: NftAsset {
+                    contract_address: Addr::unchecked("contract3"),
+                    token_id: Some("3".to_string()),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = collection_stats(&app);
+        assert_eq!(res.stats[0].stats.stored_listing_count, 1);
+        assert_eq!(res.stats[0].floor_price, Some(300));
+    }
+}
+
+mod listing_queries {
+    use cw721::Expiration as Cw721Expiration;
+
+    use crate::msg::QueryMsg;
+    use crate::structs::{
+        FilteredListingsResponse, ListingCursor, ListingStatus, ListingsFilter, ListingsResponse,
+        ListingsSort, SortOrder,
+    };
+
+    use super::*;
+
+    #[test]
+    fn listings_can_be_filtered_sorted_and_paginated() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection, mints tokens 1 to 4 and sends token 4 to USER_2
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["1", "2", "3", "4"] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: "contract3".to_string(),
+                    token_id: Some(token_id.to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked("contract3"),
+            &cw721::Cw721ExecuteMsg::TransferNft {
+                recipient: USER_2.to_string(),
+                token_id: "4".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // the listings are created one block after another, token 3 expires soon
+        let expires_at = app.block_info().height + 10;
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            300,
+            None,
+        );
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "2",
+            100,
+            None,
+        );
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "3",
+            200,
+            Some(Cw721Expiration::AtHeight(expires_at)),
+        );
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_2,
+            "contract3",
+            "4",
+            150,
+            None,
+        );
+
+        let listings = |app: &cw_multi_test::App,
+                        filter: Option<ListingsFilter>,
+                        sort: Option<ListingsSort>,
+                        start_after: Option<ListingCursor>,
+                        limit: Option<u32>|
+         -> Vec<String> {
+            let res: FilteredListingsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::Listings {
+                        contract_address: "contract3".to_string(),
+                        filter,
+                        sort,
+                        start_after,
+                        limit,
+                    },
+                )
+                .unwrap();
+            res.listings
+                .into_iter()
+                .map(|res| res.listing.order_id.2)
+                .collect()
+        };
+
+        // the cheapest listing comes first by default
+        assert_eq!(listings(&app, None, None, None, None), ["2", "4", "3", "1"]);
+        assert_eq!(
+            listings(&app, None, Some(ListingsSort::Newest), None, None),
+            ["4", "3", "2", "1"]
+        );
+
+        // the second page starts after the last listing of the first page
+        let first_page = listings(&app, None, Some(ListingsSort::PriceDesc), None, Some(2));
+        assert_eq!(first_page, ["1", "3"]);
+        let listing: crate::structs::ListingResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::Listing {
+                    contract_address: Addr::unchecked("contract3"),
+                    token_id: "3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            listings(
+                &app,
+                None,
+                Some(ListingsSort::PriceDesc),
+                Some(listing.listing.cursor()),
+                Some(2)
+            ),
+            ["4", "2"]
+        );
+
+        // the response carries the cursor of the next page, until every listing was read
+        let page = |limit: u32| -> FilteredListingsResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::Listings {
+                        contract_address: "contract3".to_string(),
+                        filter: None,
+                        sort: Some(ListingsSort::PriceDesc),
+                        start_after: None,
+                        limit: Some(limit),
+                    },
+                )
+                .unwrap()
+        };
+        assert_eq!(page(2).next, Some(listing.listing.cursor()));
+        assert_eq!(page(4).next, None);
+
+        let filter = ListingsFilter {
+            min_price: Some(150),
+            max_price: Some(250),
+            ..ListingsFilter::default()
+        };
+        assert_eq!(listings(&app, Some(filter), None, None, None), ["4", "3"]);
+        let filter = ListingsFilter {
+            seller: Some(USER_2.to_string()),
+            ..ListingsFilter::default()
+        };
+        assert_eq!(listings(&app, Some(filter), None, None, None), ["4"]);
+
+        // token 3 expires
+        app.update_block(|block| block.height = expires_at);
+        let filter = ListingsFilter {
+            status: Some(ListingStatus::Active),
+            ..ListingsFilter::default()
+        };
+        assert_eq!(
+            listings(&app, Some(filter), None, None, None),
+            ["2", "4", "1"]
+        );
+        let filter = ListingsFilter {
+            status: Some(ListingStatus::Expired),
+            ..ListingsFilter::default()
+        };
+        assert_eq!(listings(&app, Some(filter), None, None, None), ["3"]);
+
+        // the listings of the collection are found by its address, whoever the seller is
+        let res: ListingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::ListingsByContractAddress {
+                    contract_address: Addr::unchecked("contract3"),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.listings.len(), 4);
+    }
+
+    #[test]
+    fn listings_of_all_collections_can_be_enumerated() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates two collections and mints token 1 in both
+        for (name, contract_address) in [("NFT_A", "contract3"), ("NFT_B", "contract4")] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::CreateCollection {
+                    name: name.to_string(),
+                    symbol: "NFT".to_string(),
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    max_supply: None,
+                    royalty_bps: None,
+                    royalty_recipient: None,
+                    token_id_mode: None,
+                    template: None,
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: contract_address.to_string(),
+                    token_id: Some("1".to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // the token of the second collection is listed first
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract4",
+            "1",
+            100,
+            None,
+        );
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "1",
+            100,
+            None,
+        );
+
+        let collections = |res: ListingsResponse| -> Vec<String> {
+            res.listings
+                .into_iter()
+                .map(|res| res.listing.order_id.1.to_string())
+                .collect()
+        };
+
+        let res: ListingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::AllListings {
+                    start_after: None,
+                    limit: None,
+                    order: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(collections(res), ["contract3", "contract4"]);
+
+        let res: ListingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::AllListings {
+                    start_after: Some((Addr::unchecked("contract4"), "1".to_string())),
+                    limit: None,
+                    order: Some(SortOrder::Descending),
+                },
+            )
+            .unwrap();
+        assert_eq!(collections(res), ["contract3"]);
+
+        // the most recently listed token comes first
+        let res: ListingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::RecentListings {
+                    start_after: None,
+                    limit: Some(1),
+                },
+            )
+            .unwrap();
+        let last = &res.listings[0].listing;
+        let cursor = (
+            last.height,
+            last.order_id.1.clone(),
+            last.order_id.2.clone(),
+        );
+        assert_eq!(collections(res), ["contract3"]);
+
+        let res: ListingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::RecentListings {
+                    start_after: Some(cursor),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(collections(res), ["contract4"]);
+    }
+}
+
+mod sweep_expired {
+    use cosmwasm_std::Coin;
+    use cw721::Expiration as Cw721Expiration;
+
+    use crate::error::ContractError;
+    use crate::msg::QueryMsg;
+    use crate::structs::{CollectionStatsResponse, ListingsResponse};
+    use crate::test_setup::env::{native_balance, OWNER};
+
+    use super::*;
+
+    #[test]
+    fn anyone_can_sweep_expired_listings() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection and mints tokens 1 to 4
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["1", "2", "3", "4"] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: "contract3".to_string(),
+                    token_id: Some(token_id.to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // tokens 1 and 2 expire at a height, token 3 at a time and token 4 never
+        let block = app.block_info();
+        let end_times = [
+            ("1", Some(Cw721Expiration::AtHeight(block.height + 20))),
+            ("2", Some(Cw721Expiration::AtHeight(block.height + 10))),
+            (
+                "3",
+                Some(Cw721Expiration::AtTime(block.time.plus_seconds(100))),
+            ),
+            ("4", None),
+        ];
+        for (token_id, end_time) in end_times {
+            list(
+                &mut app,
+                &marketplace_address,
+                USER_1,
+                "contract3",
+                token_id,
+                100,
+                end_time,
+            );
+        }
+
+        // nothing is expired yet
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER_2),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::SweepExpired { limit: None },
+                &[],
+            )
+            .unwrap();
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "swept" && attr.value == "0")));
+
+        // every end_time has passed, a sweep of 2 removes the listings expiring at a height
+        app.update_block(|block| {
+            block.height += 100;
+            block.time = block.time.plus_seconds(1_000);
+        });
+        app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::SweepExpired { limit: Some(2) },
+            &[],
+        )
+        .unwrap();
+
+        let listed = |app: &cw_multi_test::App| -> Vec<String> {
+            let res: ListingsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::ListingsByContractAddress {
+                        contract_address: Addr::unchecked("contract3"),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            res.listings
+                .into_iter()
+                .map(|res| res.listing.order_id.2)
+                .collect()
+        };
+        assert_eq!(listed(&app), ["3", "4"]);
+
+        app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::SweepExpired { limit: None },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(listed(&app), ["4"]);
+
+        let res: CollectionStatsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_address.clone(),
+                &QueryMsg::CollectionStats {
+                    contract_address: "contract3".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.stats[0].stats.stored_listing_count, 1);
+    }
+
+    #[test]
+    fn floor_skips_every_expired_listing() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // USER_1 creates collection and mints tokens 0 to 120
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in 0..=120 {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: "contract3".to_string(),
+                    token_id: Some(token_id.to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // the 120 cheapest listings expire, the most expensive one never does
+        let end_height = app.block_info().height + 1_000;
+        for token_id in 0..120u128 {
+            list(
+                &mut app,
+                &marketplace_address,
+                USER_1,
+                "contract3",
+                &token_id.to_string(),
+                100 + token_id,
+                Some(Cw721Expiration::AtHeight(end_height)),
+            );
+        }
+        list(
+            &mut app,
+            &marketplace_address,
+            USER_1,
+            "contract3",
+            "120",
+            1_000,
+            None,
+        );
+        app.update_block(|block| block.height = end_height);
+
+        let stats = |app: &cw_multi_test::App| -> CollectionStatsResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_address.clone(),
+                    &QueryMsg::CollectionStats {
+                        contract_address: "contract3".to_string(),
+                    },
+                )
+                .unwrap()
+        };
+        let res = stats(&app);
+        assert_eq!(res.stats[0].floor_price, Some(1_000));
+        // the expired listings are stored until they are swept
+        assert_eq!(res.stats[0].stats.stored_listing_count, 121);
+
+        for _ in 0..2 {
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::SweepExpired { limit: Some(100) },
+                &[],
+            )
+            .unwrap();
+        }
+        let res = stats(&app);
+        assert_eq!(res.stats[0].floor_price, Some(1_000));
+        assert_eq!(res.stats[0].stats.stored_listing_count, 1);
+    }
+
+    #[test]
+    fn listing_bond_is_refunded_or_paid_to_the_pruner() {
+        // get integration test app and contracts
+        let (mut app, contracts) = instantiate_contracts();
+        let marketplace_address = contracts[1].contract_addr.clone();
+
+        // the owner requires a bond of 1000 uaura for every listing
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::UpdateConfig {
+                listing_mode: None,
+                listing_bond: Some(Coin::new(1000, NATIVE_DENOM)),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // USER_1 creates collection and mints tokens 1 and 2
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::CreateCollection {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty_bps: None,
+                royalty_recipient: None,
+                token_id_mode: None,
+                template: None,
+            },
+            &[],
+        )
+        .unwrap();
+        for token_id in ["1", "2"] {
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &ExecuteMsg::MintNft {
+                    contract_address: "contract3".to_string(),
+                    token_id: Some(token_id.to_string()),
+                    token_uri: "https://www.google.com".to_string(),
+                    extension: None,
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked("contract3"),
+                &cw721::Cw721ExecuteMsg::Approve {
+                    spender: marketplace_address.clone(),
+                    token_id: token_id.to_string(),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let list_msg = |token_id: &str, end_time: Option<Cw721Expiration>| ExecuteMsg::ListNft {
+            asset: NftAsset {
+                contract_address: Addr::unchecked("contract3"),
+                token_id: Some(token_id.to_string()),
+            },
+            listing_config: AuctionConfig::FixedPrice {
+                price: PaymentAsset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: 100,
+                },
+                start_time: None,
+                end_time,
+                merkle_root: None,
+            },
+        };
+        let balance = |app: &cw_multi_test::App, address: &str| native_balance(app, address).u128();
+
+        // the listing is refused without the bond
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER_1),
+                Addr::unchecked(marketplace_address.clone()),
+                &list_msg("1", None),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::ListingBondRequired { .. }
+        ));
+
+        // token 1 never expires, token 2 expires soon
+        let user_1_balance = balance(&app, USER_1);
+        let expires_at = app.block_info().height + 10;
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_msg("1", None),
+            &[Coin::new(1000, NATIVE_DENOM)],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &list_msg("2", Some(Cw721Expiration::AtHeight(expires_at))),
+            &[Coin::new(1000, NATIVE_DENOM)],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, USER_1), user_1_balance - 2000);
+
+        // the seller gets the bond back when cancelling
+        app.execute_contract(
+            Addr::unchecked(USER_1),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::Cancel {
+                asset: NftAsset {
+                    contract_address: Addr::unchecked("contract3"),
+                    token_id: Some("1".to_string()),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, USER_1), user_1_balance - 1000);
+
+        // USER_2 sweeps the expired listing and gets its bond
+        let user_2_balance = balance(&app, USER_2);
+        app.update_block(|block| block.height = expires_at);
+        app.execute_contract(
+            Addr::unchecked(USER_2),
+            Addr::unchecked(marketplace_address.clone()),
+            &ExecuteMsg::SweepExpired { limit: None },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, USER_2), user_2_balance + 1000);
+        assert_eq!(balance(&app, USER_1), user_1_balance - 1000);
     }
 }
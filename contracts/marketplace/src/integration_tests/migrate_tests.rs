@@ -0,0 +1,197 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::{
+    to_json_binary, Addr, ContractInfoResponse, ContractResult, Order as StdOrder, StdResult,
+    Storage, SystemResult, WasmQuery,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::{Item, Map};
+
+use crate::contract::{migrate, CONTRACT_NAME, CONTRACT_VERSION};
+use crate::msg::MigrateMsg;
+use crate::state::{listing_key, ListingKey, COLLECTIONS, COLLECTION_TEMPLATES, CONFIG, LISTINGS};
+use crate::structs::{
+    order_id, CollectionKind, ListingMode, Order, OrderType, DEFAULT_COLLECTION_TEMPLATE,
+};
+use crate::test_setup::env::{OWNER, USER_1};
+use crate::ContractError;
+
+// version 0.0.1 stored the code id of the collections in the config
+fn save_legacy_config(storage: &mut dyn Storage) {
+    let raw_config: Item<serde_json::Value> = Item::new("config");
+    raw_config
+        .save(
+            storage,
+            &serde_json::json!({ "owner": OWNER, "collection_code_id": 7 }),
+        )
+        .unwrap();
+}
+
+#[test]
+fn cannot_migrate_from_another_contract() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, "crates.io:cw721-base", "0.0.1").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+    assert!(matches!(
+        res,
+        Err(ContractError::InvalidContractName { .. })
+    ));
+}
+
+#[test]
+fn cannot_downgrade_contract() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+    assert!(matches!(res, Err(ContractError::CannotDowngrade { .. })));
+
+    // the stored version is untouched
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, "99.0.0");
+}
+
+#[test]
+fn migrate_from_0_0_1_reindexes_listings() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.1").unwrap();
+    save_legacy_config(deps.as_mut().storage);
+
+    // version 0.0.1 only wrote the primary key and the contract_address index
+    let seller = Addr::unchecked(USER_1);
+    let contract_address = Addr::unchecked("contract3");
+    let listing = Order {
+        order_type: OrderType::LISTING,
+        order_id: order_id(&seller, &contract_address, "1"),
+        owner: seller.clone(),
+        offer: vec![],
+        consideration: vec![],
+        start_time: None,
+        end_time: None,
+        merkle_root: None,
+        height: 0,
+        version: 0,
+        bond: None,
+    };
+    let raw_listings: Map<ListingKey, Order> = Map::new("listings");
+    raw_listings
+        .save(
+            deps.as_mut().storage,
+            listing_key(&contract_address, &"1".to_string()),
+            &listing,
+        )
+        .unwrap();
+    let legacy_index: Map<Vec<u8>, u32> = Map::new("listings__contract_address");
+    legacy_index
+        .save(deps.as_mut().storage, seller.as_bytes().to_vec(), &1)
+        .unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "applied_steps" && attr.value == "0.1.0"));
+
+    // the listing can now be found by its seller
+    let listings = LISTINGS
+        .idx
+        .users
+        .prefix(seller)
+        .range(deps.as_ref().storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(listings.len(), 1);
+
+    // the old contract_address index, keyed by the seller, is removed
+    let legacy_keys = legacy_index
+        .keys(deps.as_ref().storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert!(legacy_keys.is_empty());
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+}
+
+#[test]
+fn migrate_from_0_0_1_converts_collections() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.1").unwrap();
+    save_legacy_config(deps.as_mut().storage);
+
+    // the name and symbol are read from the collection contract, the code id from the chain
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&cw721::ContractInfoResponse {
+                name: "NFT_A".to_string(),
+                symbol: "NFT".to_string(),
+            })
+            .unwrap(),
+        )),
+        WasmQuery::ContractInfo { .. } => {
+            let mut contract_info = ContractInfoResponse::default();
+            contract_info.code_id = 1;
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&contract_info).unwrap()))
+        }
+        // the cw2 version of the collection
+        WasmQuery::Raw { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&cw2::ContractVersion {
+                contract: "crates.io:cw721-base".to_string(),
+                version: "0.18.0".to_string(),
+            })
+            .unwrap(),
+        )),
+        _ => panic!("unexpected query"),
+    });
+
+    // version 0.0.1 mapped the collection address to the minter address
+    let raw_collections: Map<String, String> = Map::new("collections");
+    raw_collections
+        .save(
+            deps.as_mut().storage,
+            "contract3".to_string(),
+            &USER_1.to_string(),
+        )
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let collection = COLLECTIONS
+        .load(deps.as_ref().storage, &Addr::unchecked("contract3"))
+        .unwrap();
+    assert_eq!(collection.name, "NFT_A");
+    assert_eq!(collection.symbol, "NFT");
+    assert_eq!(collection.creator, Addr::unchecked(USER_1));
+    assert_eq!(collection.code_id, Some(1));
+    assert_eq!(collection.code_version, Some("0.18.0".to_string()));
+
+    // the collection can be found by its creator
+    let collections = COLLECTIONS
+        .idx
+        .creator
+        .prefix(Addr::unchecked(USER_1))
+        .keys(deps.as_ref().storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(collections, vec![Addr::unchecked("contract3")]);
+}
+
+#[test]
+fn migrate_from_0_0_1_converts_config() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.1").unwrap();
+    save_legacy_config(deps.as_mut().storage);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert!(config.is_owner(&Addr::unchecked(OWNER)));
+    assert_eq!(config.listing_mode, ListingMode::Open);
+
+    // the collection code id becomes the default template
+    let template = COLLECTION_TEMPLATES
+        .load(deps.as_ref().storage, DEFAULT_COLLECTION_TEMPLATE)
+        .unwrap();
+    assert_eq!(template.code_id, 7);
+    assert_eq!(template.kind, CollectionKind::Standard);
+}
@@ -1,3 +1,9 @@
 #![cfg(test)]
 #[cfg(test)]
+pub mod admin_tests;
+#[cfg(test)]
+pub mod launchpad_tests;
+#[cfg(test)]
 pub mod listing_tests;
+#[cfg(test)]
+pub mod migrate_tests;
@@ -1,6 +1,8 @@
 pub mod contract;
 pub mod error;
+pub mod events;
 pub mod execute;
+pub mod migrations;
 pub mod msg;
 pub mod query;
 pub mod state;
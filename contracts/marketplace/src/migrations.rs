@@ -0,0 +1,153 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, DepsMut, Env, Order as StdOrder, StdResult};
+use cw721::{ContractInfoResponse, Cw721QueryMsg};
+use cw_storage_plus::{Item, Map};
+use semver::Version;
+
+use crate::{
+    error::ContractError,
+    execute::{collection_version, update_listing_count},
+    state::{ListingKey, COLLECTIONS, COLLECTION_TEMPLATES, CONFIG, LISTINGS},
+    structs::{
+        CollectionInfo, CollectionKind, CollectionTemplate, Config, ListingMode, Order,
+        TokenIdMode, DEFAULT_COLLECTION_TEMPLATE,
+    },
+};
+
+// a migration step upgrades the storage written by the previous version
+type MigrationStep = fn(DepsMut, &Env) -> Result<(), ContractError>;
+
+// the list of migration steps, ordered by the version that introduced them
+// !DO NOT reorder or remove the steps, only append new ones
+const MIGRATIONS: &[(&str, MigrationStep)] = &[
+    ("0.1.0", reindex_listings),
+    ("0.1.0", migrate_collections),
+    ("0.1.0", migrate_config),
+];
+
+// run every step introduced after the stored version, up to the new version
+pub fn run_migrations(
+    mut deps: DepsMut,
+    env: &Env,
+    stored_version: &Version,
+    new_version: &Version,
+) -> Result<Vec<String>, ContractError> {
+    let mut applied: Vec<String> = vec![];
+    for (version, step) in MIGRATIONS {
+        let step_version = Version::parse(version)?;
+        if *stored_version < step_version && step_version <= *new_version {
+            step(deps.branch(), env)?;
+            if !applied.contains(&version.to_string()) {
+                applied.push(version.to_string());
+            }
+        }
+    }
+    Ok(applied)
+}
+
+// listings saved before 0.1.0 were never written to the seller index,
+// so we save every listing again to rebuild all listing indexes and count them in the stats
+fn reindex_listings(deps: DepsMut, _env: &Env) -> Result<(), ContractError> {
+    // before 0.1.0, the contract_address index was keyed by the seller instead of the collection,
+    // it is rebuilt as listings__collection, so the entries of the old index are removed
+    let legacy_index: Map<Vec<u8>, u32> = Map::new("listings__contract_address");
+    let legacy_keys = legacy_index
+        .keys(deps.storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for key in legacy_keys {
+        legacy_index.remove(deps.storage, key);
+    }
+
+    let listings = LISTINGS
+        .range(deps.storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<(ListingKey, Order)>>>()?;
+
+    for (key, listing) in listings {
+        // there is no old data, so the existing index entries are overwritten in place
+        LISTINGS.replace(deps.storage, key, Some(&listing), None)?;
+        update_listing_count(deps.storage, &listing, true)?;
+    }
+    Ok(())
+}
+
+// before 0.1.0, collections mapped the collection address to the minter address
+fn migrate_collections(deps: DepsMut, _env: &Env) -> Result<(), ContractError> {
+    let legacy_collections: Map<String, String> = Map::new("collections");
+    let collections = legacy_collections
+        .range(deps.storage, None, None, StdOrder::Ascending)
+        .collect::<StdResult<Vec<(String, String)>>>()?;
+
+    for (contract_address, minter) in collections {
+        // the typed map reads the old value when saving, so the legacy value must go first
+        legacy_collections.remove(deps.storage, contract_address.clone());
+
+        // the reply ids were only stored while the collection was instantiated
+        if contract_address.parse::<u64>().is_ok() {
+            continue;
+        }
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let contract_info: ContractInfoResponse = deps
+            .querier
+            .query_wasm_smart(&contract_address, &Cw721QueryMsg::ContractInfo {})?;
+        let code_id = deps
+            .querier
+            .query_wasm_contract_info(&contract_address)?
+            .code_id;
+
+        // the creation height was not recorded before 0.1.0
+        COLLECTIONS.save(
+            deps.storage,
+            &contract_address,
+            &CollectionInfo {
+                name: contract_info.name,
+                symbol: contract_info.symbol,
+                creator: deps.api.addr_validate(&minter)?,
+                created_at: 0,
+                description: None,
+                image: None,
+                external_link: None,
+                max_supply: None,
+                royalty: None,
+                token_id_mode: TokenIdMode::Manual,
+                kind: CollectionKind::Standard,
+                code_id: Some(code_id),
+                code_version: collection_version(&deps.querier, &contract_address),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// the config before 0.1.0
+#[cw_serde]
+struct LegacyConfig {
+    owner: Addr,
+    collection_code_id: u64,
+}
+
+// before 0.1.0, the collections were all created from the collection_code_id of the config,
+// it becomes the default collection template
+fn migrate_config(deps: DepsMut, _env: &Env) -> Result<(), ContractError> {
+    let legacy_config: LegacyConfig = Item::new("config").load(deps.storage)?;
+
+    COLLECTION_TEMPLATES.save(
+        deps.storage,
+        DEFAULT_COLLECTION_TEMPLATE,
+        &CollectionTemplate {
+            name: DEFAULT_COLLECTION_TEMPLATE.to_string(),
+            code_id: legacy_config.collection_code_id,
+            kind: CollectionKind::Standard,
+        },
+    )?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner: Some(legacy_config.owner),
+            listing_mode: ListingMode::Open,
+            protocol_fee: None,
+            listing_bond: None,
+        },
+    )?;
+    Ok(())
+}
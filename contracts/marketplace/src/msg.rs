@@ -1,12 +1,20 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp};
+use cw721::Expiration;
 
-use crate::structs::{AuctionConfig, Config, ListingsResponse, NftAsset, Order};
+use crate::structs::{
+    AuctionConfig, CollectionKind, CollectionMintersResponse, CollectionRegistration,
+    CollectionResponse, CollectionStatsResponse, CollectionTemplate, CollectionTemplatesResponse,
+    CollectionsResponse, Config, FilteredListingsResponse, ListingCursor, ListingMode,
+    ListingResponse, ListingsFilter, ListingsResponse, ListingsSort, MerkleProof, Metadata,
+    MintItem, MintPhasesResponse, NftAsset, PausableOperation, PauseStatus, PaymentAsset, Role,
+    RoleHoldersResponse, SalesFilter, SalesResponse, SortOrder, TokenIdMode, WalletMintsResponse,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub owner: Addr,
-    pub collection_code_id: u64,
+    pub collection_templates: Vec<CollectionTemplate>,
 }
 
 #[cw_serde]
@@ -16,29 +24,172 @@ pub enum ExecuteMsg {
         asset: NftAsset,
         listing_config: AuctionConfig,
     },
+    // Seller changes the price or the end_time of a listing, the fields not provided are unchanged
+    UpdateListing {
+        asset: NftAsset,
+        price: Option<PaymentAsset>,
+        end_time: Option<Expiration>,
+    },
     // Buy a listed NFT
     Buy {
         asset: NftAsset,
+        // required to buy a private listing
+        proof: Option<MerkleProof>,
     },
     // Cancel a listed NFT
     Cancel {
         asset: NftAsset,
     },
+    // remove the expired listings, anyone can sweep them
+    SweepExpired {
+        limit: Option<u32>,
+    },
     // User creates a new collection
     CreateCollection {
         name: String,
         symbol: String,
+        description: Option<String>,
+        image: Option<String>,
+        external_link: Option<String>,
+        max_supply: Option<u64>,
+        // the royalty recipient is the creator if not provided
+        royalty_bps: Option<u16>,
+        royalty_recipient: Option<String>,
+        // the token ids are provided by the minter if not set
+        token_id_mode: Option<TokenIdMode>,
+        // the name of the collection template, "standard" if not set
+        template: Option<String>,
     },
     // User mints a new NFT
+    // the token_id must be empty if the collection assigns sequential token ids
+    // the extension is only accepted by collections storing the metadata on-chain
     MintNft {
         contract_address: String,
-        token_id: String,
+        token_id: Option<String>,
         token_uri: String,
+        extension: Option<Metadata>,
+    },
+    // User mints many NFTs at once, directly to their owners
+    BatchMint {
+        contract_address: String,
+        tokens: Vec<MintItem>,
+    },
+    // Creator hands the collection over to a new creator,
+    // the owner and the collection curators can also recover a collection whose creator is lost
+    TransferCollectionCreator {
+        contract_address: String,
+        new_creator: String,
+    },
+    // Creator allows another address to mint in the collection
+    AddMinter {
+        contract_address: String,
+        minter: String,
+    },
+    // Creator revokes a minter of the collection
+    RemoveMinter {
+        contract_address: String,
+        minter: String,
+    },
+    // Owner or creator migrates created collections to a new code id
+    MigrateCollections {
+        code_id: u64,
+        collections: Vec<String>,
+        msg: Binary,
+    },
+    // Creator opens a new mint phase for a collection with sequential token ids
+    AddMintPhase {
+        contract_address: String,
+        start_time: Timestamp,
+        end_time: Timestamp,
+        price: PaymentAsset,
+        max_per_wallet: Option<u32>,
+        // only these addresses can mint if set, more can be added with UpdateMintPhaseAllowlist
+        allowlist: Option<Vec<String>>,
+        base_token_uri: String,
+        // the addresses proving their allocation in this merkle tree can also mint
+        merkle_root: Option<String>,
+    },
+    // Creator adds or removes addresses of the allowlist of a mint phase
+    UpdateMintPhaseAllowlist {
+        contract_address: String,
+        phase_id: u32,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // Creator closes a mint phase
+    RemoveMintPhase {
+        contract_address: String,
+        phase_id: u32,
+    },
+    // User buys a new token in an active mint phase
+    PublicMint {
+        contract_address: String,
+        phase_id: u32,
+        // required to mint in a phase with a merkle root when not in the allowlist
+        proof: Option<MerkleProof>,
+    },
+    // Fee manager sets the share of the primary sales paid to the fee recipient
+    UpdateProtocolFee {
+        fee_bps: u16,
+        fee_recipient: String,
     },
     // Admin allows payment token to be used for payment
     AllowPaymentToken {
         contract_address: Addr,
     },
+    // Admin updates the config, the fields not provided are unchanged
+    UpdateConfig {
+        listing_mode: Option<ListingMode>,
+        // a zero amount removes the listing bond
+        listing_bond: Option<Coin>,
+    },
+    // Admin adds or replaces a collection template
+    SetCollectionTemplate {
+        name: String,
+        code_id: u64,
+        kind: CollectionKind,
+    },
+    // Admin removes a collection template, the collections already created are unchanged
+    RemoveCollectionTemplate {
+        name: String,
+    },
+    // Admin proposes a new owner, who must accept the ownership
+    ProposeNewOwner {
+        new_owner: String,
+    },
+    // The proposed owner accepts the ownership
+    AcceptOwnership {},
+    // Admin gives up the ownership, no one can administer the contract afterwards
+    RenounceOwnership {},
+    // Collection curator adds or updates a collection in the registry
+    RegisterCollection {
+        contract_address: String,
+        verified: bool,
+        display_name: String,
+        payment_denoms: Option<Vec<String>>,
+    },
+    // Collection curator removes a collection from the registry
+    UnregisterCollection {
+        contract_address: String,
+    },
+    // Admin grants a role to an address
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    // Admin revokes a role from an address
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    // Admin or a holder of the pauser role stops the operations, cancelling a listing is always allowed
+    Pause {
+        operations: Vec<PausableOperation>,
+    },
+    // Admin or a holder of the pauser role resumes the operations
+    Unpause {
+        operations: Vec<PausableOperation>,
+    },
 }
 
 #[cw_serde]
@@ -58,9 +209,92 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
     // get listing by contract_address and token_id
-    #[returns(Order)]
+    #[returns(ListingResponse)]
     Listing {
         contract_address: Addr,
         token_id: String,
     },
+    // get a collection created by this contract
+    #[returns(CollectionResponse)]
+    Collection { contract_address: String },
+    // list the collections created by this contract
+    #[returns(CollectionsResponse)]
+    Collections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // list the collections created by a user
+    #[returns(CollectionsResponse)]
+    CollectionsByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // list the listings of a collection matching the filter, the cheapest first by default
+    #[returns(FilteredListingsResponse)]
+    Listings {
+        contract_address: String,
+        filter: Option<ListingsFilter>,
+        sort: Option<ListingsSort>,
+        start_after: Option<ListingCursor>,
+        limit: Option<u32>,
+    },
+    // page through the listings of all collections in key order
+    #[returns(ListingsResponse)]
+    AllListings {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    // page through the listings of all collections, the most recently listed first
+    // the cursor is the listing height, the contract address and the token id of the last listing
+    #[returns(ListingsResponse)]
+    RecentListings {
+        start_after: Option<(u64, Addr, String)>,
+        limit: Option<u32>,
+    },
+    // get the trading stats and the floor price of a collection for each payment denom
+    #[returns(CollectionStatsResponse)]
+    CollectionStats { contract_address: String },
+    // list the completed sales matching the filter, the most recent first
+    #[returns(SalesResponse)]
+    SalesHistory {
+        filter: SalesFilter,
+        // the sale id of the last sale of the previous page
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // list the templates the collections can be created from
+    #[returns(CollectionTemplatesResponse)]
+    CollectionTemplates {},
+    // list the minters of a collection besides its creator
+    #[returns(CollectionMintersResponse)]
+    CollectionMinters {
+        contract_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // list the mint phases of a collection
+    #[returns(MintPhasesResponse)]
+    MintPhases { contract_address: String },
+    // get the number of tokens minted by a wallet in a mint phase
+    #[returns(WalletMintsResponse)]
+    WalletMints {
+        contract_address: String,
+        phase_id: u32,
+        address: String,
+    },
+    // get a collection from the registry
+    #[returns(CollectionRegistration)]
+    CollectionRegistration { contract_address: Addr },
+    // get the paused state of every operation
+    #[returns(PauseStatus)]
+    PauseStatus {},
+    // list the addresses holding a role
+    #[returns(RoleHoldersResponse)]
+    RoleHolders {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
@@ -1,14 +1,37 @@
-use cosmwasm_std::{Addr, Deps, Order, StdResult};
+use cosmwasm_std::{Addr, BlockInfo, Deps, Env, Order, StdResult};
 use cw_storage_plus::Bound;
 
 use crate::{
-    state::{listing_key, ListingKey, LISTINGS},
-    structs::{ListingsResponse, Order as Listing},
+    state::{
+        listing_key, ListingKey, COLLECTIONS, COLLECTION_MINTERS, COLLECTION_REGISTRY,
+        COLLECTION_STATS, COLLECTION_TEMPLATES, LISTINGS, MINTED_COUNT, MINT_PHASES,
+        MINT_PHASE_ALLOWLIST, MINT_PHASE_WALLET_MINTS, PAUSE_STATUS, ROLES, SALES,
+    },
+    structs::{
+        CollectionInfo, CollectionMintersResponse, CollectionRegistration, CollectionResponse,
+        CollectionStatsResponse, CollectionTemplatesResponse, CollectionsResponse,
+        DenomStatsResponse, FilteredListingsResponse, ListingCursor, ListingResponse,
+        ListingStatus, ListingsFilter, ListingsResponse, ListingsSort, MintPhaseResponse,
+        MintPhasesResponse, PauseStatus, Role, RoleHoldersResponse, SalesFilter, SalesResponse,
+        SortOrder, WalletMintsResponse,
+    },
 };
 
-pub fn query_listing(deps: Deps, contract_address: Addr, token_id: String) -> StdResult<Listing> {
+// the most listings read by a query which skips some of them, e.g. the expired listings,
+// it keeps the gas of the query bounded whatever the number of listings
+const MAX_LISTINGS_SCAN: usize = 100;
+
+pub fn query_listing(
+    deps: Deps,
+    contract_address: Addr,
+    token_id: String,
+) -> StdResult<ListingResponse> {
     let listing_key = listing_key(&contract_address, &token_id);
-    LISTINGS.load(deps.storage, listing_key)
+    let listing = LISTINGS.load(deps.storage, listing_key)?;
+    Ok(ListingResponse {
+        listing,
+        verified: is_verified(deps, &contract_address)?,
+    })
 }
 
 pub fn query_listings_by_contract_address(
@@ -18,6 +41,7 @@ pub fn query_listings_by_contract_address(
     limit: Option<u32>,
 ) -> StdResult<ListingsResponse> {
     let limit = limit.unwrap_or(30).min(30) as usize;
+    let verified = is_verified(deps, &contract_address)?;
     let start: Option<Bound<ListingKey>> =
         start_after.map(|token_id| Bound::exclusive(listing_key(&contract_address, &token_id)));
     let listings = LISTINGS
@@ -25,8 +49,419 @@ pub fn query_listings_by_contract_address(
         .contract_address
         .prefix(contract_address)
         .range(deps.storage, start, None, Order::Ascending)
-        .map(|item| item.map(|(_, listing)| listing))
+        .map(|item| item.map(|(_, listing)| ListingResponse { listing, verified }))
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ListingsResponse { listings })
+}
+
+pub fn query_all_listings(
+    deps: Deps,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<ListingsResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let bound = start_after.map(Bound::exclusive);
+    let (start, end, order) = match order.unwrap_or_default() {
+        SortOrder::Ascending => (bound, None, Order::Ascending),
+        SortOrder::Descending => (None, bound, Order::Descending),
+    };
+    let listings = LISTINGS
+        .range(deps.storage, start, end, order)
         .take(limit)
+        .map(|item| {
+            item.and_then(|((contract_address, _), listing)| {
+                Ok(ListingResponse {
+                    listing,
+                    verified: is_verified(deps, &contract_address)?,
+                })
+            })
+        })
         .collect::<StdResult<Vec<_>>>()?;
     Ok(ListingsResponse { listings })
 }
+
+pub fn query_recent_listings(
+    deps: Deps,
+    start_after: Option<(u64, Addr, String)>,
+    limit: Option<u32>,
+) -> StdResult<ListingsResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let end = start_after.map(|(height, contract_address, token_id)| {
+        Bound::exclusive((height, (contract_address, token_id)))
+    });
+    let listings = LISTINGS
+        .idx
+        .recent
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.and_then(|((contract_address, _), listing)| {
+                Ok(ListingResponse {
+                    listing,
+                    verified: is_verified(deps, &contract_address)?,
+                })
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ListingsResponse { listings })
+}
+
+pub fn query_listings(
+    deps: Deps,
+    env: Env,
+    contract_address: String,
+    filter: Option<ListingsFilter>,
+    sort: Option<ListingsSort>,
+    start_after: Option<ListingCursor>,
+    limit: Option<u32>,
+) -> StdResult<FilteredListingsResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let filter = filter.unwrap_or_default();
+    let seller = filter
+        .seller
+        .as_ref()
+        .map(|seller| deps.api.addr_validate(seller))
+        .transpose()?;
+    let verified = is_verified(deps, &contract_address)?;
+
+    let listings: Box<dyn Iterator<Item = StdResult<(ListingKey, crate::structs::Order)>>> =
+        match sort.unwrap_or_default() {
+            ListingsSort::Newest => {
+                let end = start_after.map(|cursor| {
+                    Bound::exclusive((
+                        cursor.height,
+                        listing_key(&contract_address, &cursor.token_id),
+                    ))
+                });
+                LISTINGS
+                    .idx
+                    .height
+                    .sub_prefix(contract_address.clone())
+                    .range(deps.storage, None, end, Order::Descending)
+            }
+            sort => {
+                let order = match sort {
+                    ListingsSort::PriceDesc => Order::Descending,
+                    _ => Order::Ascending,
+                };
+                // prices in different denoms cannot be compared, so the listings are grouped by denom
+                let denoms = match &filter.denom {
+                    Some(denom) => vec![denom.clone()],
+                    None => COLLECTION_STATS
+                        .prefix(&contract_address)
+                        .keys(deps.storage, None, None, Order::Ascending)
+                        .collect::<StdResult<Vec<_>>>()?,
+                };
+                let mut pages = vec![];
+                for denom in denoms {
+                    // the denoms before the cursor were already returned
+                    if start_after
+                        .as_ref()
+                        .is_some_and(|cursor| denom < cursor.denom)
+                    {
+                        continue;
+                    }
+                    let bound = start_after
+                        .as_ref()
+                        .filter(|cursor| cursor.denom == denom)
+                        .map(|cursor| {
+                            Bound::exclusive((
+                                cursor.price,
+                                listing_key(&contract_address, &cursor.token_id),
+                            ))
+                        });
+                    let (start, end) = match order {
+                        Order::Ascending => (bound, None),
+                        Order::Descending => (None, bound),
+                    };
+                    pages.push(
+                        LISTINGS
+                            .idx
+                            .price
+                            .sub_prefix((contract_address.clone(), denom))
+                            .range(deps.storage, start, end, order),
+                    );
+                }
+                Box::new(pages.into_iter().flatten())
+            }
+        };
+
+    // the filter is applied after reading the listings, so at most MAX_LISTINGS_SCAN listings
+    // are read per page, the next page starts after the last listing read even if it did not match
+    let mut page = vec![];
+    let mut last = None;
+    let mut next = None;
+    for (scanned, item) in listings.enumerate() {
+        // there is a next page only if a listing is left
+        if page.len() == limit || scanned == MAX_LISTINGS_SCAN {
+            next = last;
+            break;
+        }
+        let (_, listing) = item?;
+        last = Some(listing.cursor());
+        if matches_filter(&listing, &filter, seller.as_ref(), &env.block) {
+            page.push(ListingResponse { listing, verified });
+        }
+    }
+    Ok(FilteredListingsResponse {
+        listings: page,
+        next,
+    })
+}
+
+fn matches_filter(
+    listing: &crate::structs::Order,
+    filter: &ListingsFilter,
+    seller: Option<&Addr>,
+    block: &BlockInfo,
+) -> bool {
+    let (denom, price) = listing.price_key();
+    filter.denom.as_ref().is_none_or(|d| *d == denom)
+        && filter.min_price.is_none_or(|min_price| price >= min_price)
+        && filter.max_price.is_none_or(|max_price| price <= max_price)
+        && seller.is_none_or(|seller| listing.owner == *seller)
+        && filter
+            .status
+            .as_ref()
+            .is_none_or(|status| listing.status(block) == *status)
+}
+
+pub fn query_collection(deps: Deps, contract_address: String) -> StdResult<CollectionResponse> {
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let collection = COLLECTIONS.load(deps.storage, &contract_address)?;
+    collection_response(deps, contract_address, collection)
+}
+
+pub fn query_collections(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CollectionsResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let collections = COLLECTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .map(|item| {
+            item.and_then(|(contract_address, collection)| {
+                collection_response(deps, contract_address, collection)
+            })
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionsResponse { collections })
+}
+
+pub fn query_collections_by_creator(
+    deps: Deps,
+    creator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CollectionsResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let creator = deps.api.addr_validate(&creator)?;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+    let collections = COLLECTIONS
+        .idx
+        .creator
+        .prefix(creator)
+        .range(deps.storage, start, None, Order::Ascending)
+        .map(|item| {
+            item.and_then(|(contract_address, collection)| {
+                collection_response(deps, contract_address, collection)
+            })
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionsResponse { collections })
+}
+
+fn collection_response(
+    deps: Deps,
+    contract_address: Addr,
+    collection: CollectionInfo,
+) -> StdResult<CollectionResponse> {
+    let minted = MINTED_COUNT
+        .may_load(deps.storage, &contract_address)?
+        .unwrap_or(0);
+    Ok(CollectionResponse {
+        contract_address,
+        collection,
+        minted,
+    })
+}
+
+pub fn query_collection_registration(
+    deps: Deps,
+    contract_address: Addr,
+) -> StdResult<CollectionRegistration> {
+    COLLECTION_REGISTRY.load(deps.storage, &contract_address)
+}
+
+// whether the collection is marked as verified in the registry
+fn is_verified(deps: Deps, contract_address: &Addr) -> StdResult<bool> {
+    Ok(COLLECTION_REGISTRY
+        .may_load(deps.storage, contract_address)?
+        .map(|registration| registration.verified)
+        .unwrap_or(false))
+}
+
+pub fn query_collection_stats(
+    deps: Deps,
+    env: Env,
+    contract_address: String,
+) -> StdResult<CollectionStatsResponse> {
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let stats = COLLECTION_STATS
+        .prefix(&contract_address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.and_then(|(denom, stats)| {
+                // the cheapest listing which can be bought now, the listings not started yet and
+                // the expired listings are skipped until SweepExpired removes the expired ones
+                let floor_price = LISTINGS
+                    .idx
+                    .price
+                    .sub_prefix((contract_address.clone(), denom.clone()))
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .map(|item| item.map(|(_, listing)| listing))
+                    .find(|listing| match listing {
+                        Ok(listing) => listing.status(&env.block) == ListingStatus::Active,
+                        Err(_) => true,
+                    })
+                    .transpose()?
+                    .map(|listing| listing.price_key().1);
+                Ok(DenomStatsResponse {
+                    denom,
+                    stats,
+                    floor_price,
+                })
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionStatsResponse { stats })
+}
+
+pub fn query_sales_history(
+    deps: Deps,
+    filter: SalesFilter,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    // the most recent sales come first
+    let end = start_after.map(Bound::exclusive);
+    let sales = match filter {
+        SalesFilter::All {} => SALES.range(deps.storage, None, end, Order::Descending),
+        SalesFilter::Collection { contract_address } => SALES
+            .idx
+            .collection
+            .prefix(deps.api.addr_validate(&contract_address)?)
+            .range(deps.storage, None, end, Order::Descending),
+        SalesFilter::Token {
+            contract_address,
+            token_id,
+        } => SALES
+            .idx
+            .token
+            .prefix((deps.api.addr_validate(&contract_address)?, token_id))
+            .range(deps.storage, None, end, Order::Descending),
+        SalesFilter::Buyer { address } => SALES
+            .idx
+            .buyer
+            .prefix(deps.api.addr_validate(&address)?)
+            .range(deps.storage, None, end, Order::Descending),
+        SalesFilter::Seller { address } => SALES
+            .idx
+            .seller
+            .prefix(deps.api.addr_validate(&address)?)
+            .range(deps.storage, None, end, Order::Descending),
+    }
+    .take(limit)
+    .map(|item| item.map(|(_, sale)| sale))
+    .collect::<StdResult<Vec<_>>>()?;
+    Ok(SalesResponse { sales })
+}
+
+pub fn query_pause_status(deps: Deps) -> StdResult<PauseStatus> {
+    Ok(PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default())
+}
+
+pub fn query_role_holders(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RoleHoldersResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let holders = ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(RoleHoldersResponse { holders })
+}
+
+pub fn query_collection_templates(deps: Deps) -> StdResult<CollectionTemplatesResponse> {
+    let templates = COLLECTION_TEMPLATES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, template)| template))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionTemplatesResponse { templates })
+}
+
+pub fn query_collection_minters(
+    deps: Deps,
+    contract_address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CollectionMintersResponse> {
+    let limit = limit.unwrap_or(30).min(30) as usize;
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let minters = COLLECTION_MINTERS
+        .prefix(&contract_address)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionMintersResponse { minters })
+}
+
+pub fn query_mint_phases(deps: Deps, contract_address: String) -> StdResult<MintPhasesResponse> {
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let phases = MINT_PHASES
+        .prefix(&contract_address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(phase_id, phase)| MintPhaseResponse { phase_id, phase }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(MintPhasesResponse { phases })
+}
+
+pub fn query_wallet_mints(
+    deps: Deps,
+    contract_address: String,
+    phase_id: u32,
+    address: String,
+) -> StdResult<WalletMintsResponse> {
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let address = deps.api.addr_validate(&address)?;
+    let minted = MINT_PHASE_WALLET_MINTS
+        .may_load(deps.storage, (&contract_address, phase_id, &address))?
+        .unwrap_or(0);
+    Ok(WalletMintsResponse {
+        minted,
+        allowlisted: MINT_PHASE_ALLOWLIST
+            .has(deps.storage, (&contract_address, phase_id, &address)),
+    })
+}
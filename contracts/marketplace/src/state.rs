@@ -1,7 +1,11 @@
 use cosmwasm_std::Addr;
+use cw721::Expiration;
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
-use crate::structs::{Config, OfferID, Order, User};
+use crate::structs::{
+    CollectionInfo, CollectionRegistration, CollectionStats, CollectionTemplate, Config, MintPhase,
+    OfferID, Order, PauseStatus, PendingCollection, Sale, User,
+};
 
 pub struct OfferIndexes<'a> {
     pub users: MultiIndex<'a, User, Order, OfferID>,
@@ -29,15 +33,43 @@ pub fn listing_key(contract_address: &Addr, token_id: &TokenId) -> ListingKey {
 pub struct ListingIndexes<'a> {
     pub contract_address: MultiIndex<'a, Addr, Order, ListingKey>,
     pub users: MultiIndex<'a, Addr, Order, ListingKey>,
+    // the listings of a collection sorted by price, for each payment denom
+    pub price: MultiIndex<'a, (Addr, String, u128), Order, ListingKey>,
+    // the listings of a collection sorted by the height they were listed at
+    pub height: MultiIndex<'a, (Addr, u64), Order, ListingKey>,
+    // the listings of all collections sorted by the height they were listed at
+    pub recent: MultiIndex<'a, u64, Order, ListingKey>,
+    // the listings sorted by their end_time, for each kind of expiration
+    pub end_time: MultiIndex<'a, (String, u64), Order, ListingKey>,
 }
 impl<'a> IndexList<Order> for ListingIndexes<'a> {
     // this method returns a list of all indexes
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Order>> + '_> {
-        let v: Vec<&dyn Index<Order>> = vec![&self.contract_address];
+        let v: Vec<&dyn Index<Order>> = vec![
+            &self.contract_address,
+            &self.users,
+            &self.price,
+            &self.height,
+            &self.recent,
+            &self.end_time,
+        ];
         Box::new(v.into_iter())
     }
 }
 
+pub const EXPIRY_AT_HEIGHT: &str = "height";
+pub const EXPIRY_AT_TIME: &str = "time";
+pub const EXPIRY_NEVER: &str = "never";
+
+// the kind and the height or time in nanoseconds of an end_time
+pub fn expiry_key(end_time: &Option<Expiration>) -> (String, u64) {
+    match end_time {
+        Some(Expiration::AtHeight(height)) => (EXPIRY_AT_HEIGHT.to_string(), *height),
+        Some(Expiration::AtTime(time)) => (EXPIRY_AT_TIME.to_string(), time.nanos()),
+        Some(Expiration::Never {}) | None => (EXPIRY_NEVER.to_string(), 0),
+    }
+}
+
 // the OfferKey includes the address and id of NFT
 // !DO NOT change the order of the fields
 pub type OfferKey = (Addr, Addr, String);
@@ -51,11 +83,16 @@ pub fn offer_key(user_address: &Addr, contract_address: &Addr, token_id: &str) -
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+// the address proposed as the next owner, waiting to accept the ownership
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+// the holders of each role, keyed by the role name and the holder address
+pub const ROLES: Map<(&str, &Addr), ()> = Map::new("roles");
+pub const PAUSE_STATUS: Item<PauseStatus> = Item::new("pause_status");
 pub const OFFERS: IndexedMap<OfferKey, Order, OfferIndexes> = IndexedMap::new(
     "offers",
     OfferIndexes {
         users: MultiIndex::new(
-            |_pk: &[u8], l: &Order| (l.order_id.0.clone()),
+            |_pk: &[u8], l: &Order| l.order_id.0.clone(),
             "offers",
             "offers__user_address",
         ),
@@ -69,19 +106,136 @@ pub const OFFERS: IndexedMap<OfferKey, Order, OfferIndexes> = IndexedMap::new(
 pub const LISTINGS: IndexedMap<ListingKey, Order, ListingIndexes> = IndexedMap::new(
     "listings",
     ListingIndexes {
+        // the order id starts with the seller, the collection is the second element
         contract_address: MultiIndex::new(
-            |_pk: &[u8], l: &Order| (l.order_id.0.clone()),
+            |_pk: &[u8], l: &Order| l.order_id.1.clone(),
             "listings",
-            "listings__contract_address",
+            "listings__collection",
         ),
         users: MultiIndex::new(
-            |_pk: &[u8], l: &Order| (l.owner.clone()),
+            |_pk: &[u8], l: &Order| l.owner.clone(),
             "listings",
             "listings__user_address",
         ),
+        price: MultiIndex::new(
+            |_pk: &[u8], l: &Order| {
+                let (denom, amount) = l.price_key();
+                (l.order_id.1.clone(), denom, amount)
+            },
+            "listings",
+            "listings__price",
+        ),
+        height: MultiIndex::new(
+            |_pk: &[u8], l: &Order| (l.order_id.1.clone(), l.height),
+            "listings",
+            "listings__collection_height",
+        ),
+        recent: MultiIndex::new(
+            |_pk: &[u8], l: &Order| l.height,
+            "listings",
+            "listings__height",
+        ),
+        end_time: MultiIndex::new(
+            |_pk: &[u8], l: &Order| expiry_key(&l.end_time),
+            "listings",
+            "listings__end_time",
+        ),
     },
 );
+// the trading stats of the collections, keyed by the collection and the payment denom
+pub const COLLECTION_STATS: Map<(&Addr, &str), CollectionStats> = Map::new("collection_stats");
 
-pub const COLLECTIONS: Map<String, String> = Map::new("collections");
+// sales can be indexed by their collection, token, buyer and seller
+pub struct SaleIndexes<'a> {
+    pub collection: MultiIndex<'a, Addr, Sale, u64>,
+    pub token: MultiIndex<'a, (Addr, String), Sale, u64>,
+    pub buyer: MultiIndex<'a, Addr, Sale, u64>,
+    pub seller: MultiIndex<'a, Addr, Sale, u64>,
+}
+impl<'a> IndexList<Sale> for SaleIndexes<'a> {
+    // this method returns a list of all indexes
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Sale>> + '_> {
+        let v: Vec<&dyn Index<Sale>> =
+            vec![&self.collection, &self.token, &self.buyer, &self.seller];
+        Box::new(v.into_iter())
+    }
+}
+
+// the completed sales, keyed by an increasing sale id
+pub const SALES: IndexedMap<u64, Sale, SaleIndexes> = IndexedMap::new(
+    "sales",
+    SaleIndexes {
+        collection: MultiIndex::new(
+            |_pk: &[u8], s: &Sale| s.contract_address.clone(),
+            "sales",
+            "sales__collection",
+        ),
+        token: MultiIndex::new(
+            |_pk: &[u8], s: &Sale| (s.contract_address.clone(), s.token_id.clone()),
+            "sales",
+            "sales__token",
+        ),
+        buyer: MultiIndex::new(
+            |_pk: &[u8], s: &Sale| s.buyer.clone(),
+            "sales",
+            "sales__buyer",
+        ),
+        seller: MultiIndex::new(
+            |_pk: &[u8], s: &Sale| s.seller.clone(),
+            "sales",
+            "sales__seller",
+        ),
+    },
+);
+// the number of sales recorded so far, the id of the last sale
+pub const SALE_COUNT: Item<u64> = Item::new("sale_count");
+
+// collections can be indexed by their creator
+pub struct CollectionIndexes<'a> {
+    pub creator: MultiIndex<'a, Addr, CollectionInfo, Addr>,
+}
+impl<'a> IndexList<CollectionInfo> for CollectionIndexes<'a> {
+    // this method returns a list of all indexes
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollectionInfo>> + '_> {
+        let v: Vec<&dyn Index<CollectionInfo>> = vec![&self.creator];
+        Box::new(v.into_iter())
+    }
+}
+
+// the collections created by this contract, keyed by the collection contract address
+pub const COLLECTIONS: IndexedMap<&Addr, CollectionInfo, CollectionIndexes> = IndexedMap::new(
+    "collections",
+    CollectionIndexes {
+        creator: MultiIndex::new(
+            |_pk: &[u8], c: &CollectionInfo| c.creator.clone(),
+            "collections",
+            "collections__creator",
+        ),
+    },
+);
+// the number of tokens minted in each created collection
+pub const MINTED_COUNT: Map<&Addr, u64> = Map::new("minted_count");
+// the collections being instantiated, keyed by the id of the reply
+pub const PENDING_COLLECTIONS: Map<u64, PendingCollection> = Map::new("pending_collections");
+// the collections being migrated, keyed by the id of the reply
+pub const PENDING_MIGRATIONS: Map<u64, Addr> = Map::new("pending_migrations");
+// the collections curated by the admins, keyed by the collection contract address
+pub const COLLECTION_REGISTRY: Map<&Addr, CollectionRegistration> = Map::new("collection_registry");
 pub const COLLECTION_ID: Item<u64> = Item::new("collection_id");
 pub const ALLOWED_TOKENS: Item<Vec<Addr>> = Item::new("allowed_tokens");
+
+// the templates the collections can be created from, keyed by their name
+pub const COLLECTION_TEMPLATES: Map<&str, CollectionTemplate> = Map::new("collection_templates");
+
+// the addresses allowed to mint in a created collection besides its creator
+pub const COLLECTION_MINTERS: Map<(&Addr, &Addr), ()> = Map::new("collection_minters");
+
+// the mint phases of the created collections, keyed by the collection and the phase id
+pub const MINT_PHASES: Map<(&Addr, u32), MintPhase> = Map::new("mint_phases");
+// the id of the next mint phase of each collection, the ids are never reused
+pub const NEXT_MINT_PHASE_ID: Map<&Addr, u32> = Map::new("next_mint_phase_id");
+// the addresses allowed to mint in a mint phase with an allowlist
+pub const MINT_PHASE_ALLOWLIST: Map<(&Addr, u32, &Addr), ()> = Map::new("mint_phase_allowlist");
+// the number of tokens minted by each wallet in a mint phase
+pub const MINT_PHASE_WALLET_MINTS: Map<(&Addr, u32, &Addr), u32> =
+    Map::new("mint_phase_wallet_mints");
@@ -1,6 +1,9 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, BlockInfo};
+use cosmwasm_std::{Addr, BlockInfo, Coin, Timestamp, Uint128};
 use cw721::Expiration;
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
 
 #[cw_serde]
 pub enum AuctionConfig {
@@ -8,6 +11,8 @@ pub enum AuctionConfig {
         price: PaymentAsset,
         start_time: Option<Expiration>, // we use expiration for convinience
         end_time: Option<Expiration>,   // it's required that start_time < end_time
+        // only the buyers proving their address is in the merkle tree can buy if set
+        merkle_root: Option<String>,
     },
     OfferPrice {
         price: NftAsset,
@@ -23,15 +28,15 @@ impl AuctionConfig {
                 price: _,
                 start_time,
                 end_time,
+                merkle_root,
             } => {
                 // if start_time or end_time is not set, we don't need to check
-                if start_time.is_some()
-                    && end_time.is_some()
-                    && start_time.unwrap() >= end_time.unwrap()
-                {
-                    return false;
+                if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+                    if start_time >= end_time {
+                        return false;
+                    }
                 }
-                true
+                merkle_root.as_deref().is_none_or(is_valid_merkle_root)
             }
             AuctionConfig::OfferPrice {
                 price: _,
@@ -39,11 +44,10 @@ impl AuctionConfig {
                 end_time,
             } => {
                 // if start_time or end_time is not set, we don't need to check
-                if start_time.is_some()
-                    && end_time.is_some()
-                    && start_time.unwrap() >= end_time.unwrap()
-                {
-                    return false;
+                if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+                    if start_time >= end_time {
+                        return false;
+                    }
                 }
                 true
             }
@@ -53,8 +57,393 @@ impl AuctionConfig {
 
 #[cw_serde]
 pub struct Config {
-    pub owner: Addr,
-    pub collection_code_id: u64,
+    // the owner is None after the ownership has been renounced
+    pub owner: Option<Addr>,
+    #[serde(default)]
+    pub listing_mode: ListingMode,
+    // the share of the primary sales paid to the fee recipient
+    #[serde(default)]
+    pub protocol_fee: Option<ProtocolFee>,
+    // the native coin paid with every listing, refunded when the listing is sold or cancelled
+    #[serde(default)]
+    pub listing_bond: Option<Coin>,
+}
+
+#[cw_serde]
+pub struct ProtocolFee {
+    // in basis points, 100 bps = 1%
+    pub bps: u16,
+    pub recipient: Addr,
+}
+
+impl ProtocolFee {
+    pub fn amount(&self, price: u128) -> u128 {
+        Uint128::from(price)
+            .multiply_ratio(self.bps, BPS_DENOMINATOR)
+            .u128()
+    }
+}
+
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+// restricts which collections can be listed
+#[cw_serde]
+#[derive(Default)]
+pub enum ListingMode {
+    // any cw721 contract can be listed
+    #[default]
+    Open,
+    // only verified collections and collections created by this contract can be listed
+    VerifiedOnly,
+}
+
+impl ListingMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ListingMode::Open => "open",
+            ListingMode::VerifiedOnly => "verified_only",
+        }
+    }
+}
+
+// a collection created by this contract
+#[cw_serde]
+pub struct CollectionInfo {
+    pub name: String,
+    pub symbol: String,
+    pub creator: Addr,
+    // the block height when the collection was created
+    pub created_at: u64,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    pub max_supply: Option<u64>,
+    pub royalty: Option<Royalty>,
+    #[serde(default)]
+    pub token_id_mode: TokenIdMode,
+    // the kind of the template the collection was created from
+    #[serde(default)]
+    pub kind: CollectionKind,
+    // the code id run by the collection contract, updated when the collection is migrated
+    #[serde(default)]
+    pub code_id: Option<u64>,
+    // the cw2 version of the collection contract, read after its instantiation and migrations
+    #[serde(default)]
+    pub code_version: Option<String>,
+}
+
+// the kind of cw721 contract instantiated by a collection template
+#[cw_serde]
+#[derive(Default)]
+pub enum CollectionKind {
+    // the standard cw721-base contract
+    #[default]
+    Standard,
+    // a cw721 contract storing the token metadata on-chain
+    OnchainMetadata,
+    // a cw721 contract whose tokens cannot be transferred, they are never listed
+    Soulbound,
+    // a cw721 contract minting numbered editions of the same artwork
+    Editions,
+}
+
+impl CollectionKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CollectionKind::Standard => "standard",
+            CollectionKind::OnchainMetadata => "onchain_metadata",
+            CollectionKind::Soulbound => "soulbound",
+            CollectionKind::Editions => "editions",
+        }
+    }
+}
+
+// a code id the collections can be created from, selected by its name
+#[cw_serde]
+pub struct CollectionTemplate {
+    pub name: String,
+    pub code_id: u64,
+    pub kind: CollectionKind,
+}
+
+#[cw_serde]
+pub struct CollectionTemplatesResponse {
+    pub templates: Vec<CollectionTemplate>,
+}
+
+// the template used when CreateCollection does not choose one
+pub const DEFAULT_COLLECTION_TEMPLATE: &str = "standard";
+
+// how the token ids of a created collection are chosen
+#[cw_serde]
+#[derive(Default)]
+pub enum TokenIdMode {
+    // the minter provides the token id
+    #[default]
+    Manual,
+    // the marketplace assigns 1, 2, 3... in the order of minting
+    Sequential,
+}
+
+impl TokenIdMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenIdMode::Manual => "manual",
+            TokenIdMode::Sequential => "sequential",
+        }
+    }
+}
+
+// a token minted by a batch, the owner receives the token directly
+#[cw_serde]
+pub struct MintItem {
+    // must be empty if the collection assigns sequential token ids
+    pub token_id: Option<String>,
+    pub token_uri: String,
+    pub owner: String,
+    pub extension: Option<Metadata>,
+}
+
+// a proof that an address and its allocation are a leaf of a merkle tree
+#[cw_serde]
+pub struct MerkleProof {
+    // the number of tokens the address can buy
+    pub allocation: u32,
+    // the hex encoded sibling hashes, from the leaf up to the root
+    pub proof: Vec<String>,
+}
+
+impl MerkleProof {
+    // the leaf is sha256(address || allocation as big-endian u32),
+    // each pair of nodes is sorted before being hashed together
+    pub fn verify(&self, merkle_root: &str, address: &Addr) -> bool {
+        let Ok(root) = hex::decode(merkle_root) else {
+            return false;
+        };
+
+        let mut hash: [u8; 32] = Sha256::new()
+            .chain_update(address.as_bytes())
+            .chain_update(self.allocation.to_be_bytes())
+            .finalize()
+            .into();
+        for sibling in self.proof.iter() {
+            let sibling: [u8; 32] = match hex::decode(sibling).map(<[u8; 32]>::try_from) {
+                Ok(Ok(sibling)) => sibling,
+                _ => return false,
+            };
+            let (first, second) = if hash <= sibling {
+                (hash, sibling)
+            } else {
+                (sibling, hash)
+            };
+            hash = Sha256::new()
+                .chain_update(first)
+                .chain_update(second)
+                .finalize()
+                .into();
+        }
+        root == hash
+    }
+}
+
+// a merkle root is a hex encoded sha256 hash
+pub fn is_valid_merkle_root(merkle_root: &str) -> bool {
+    hex::decode(merkle_root)
+        .map(|root| root.len() == 32)
+        .unwrap_or(false)
+}
+
+// a sale of new tokens of a created collection, only open between start_time and end_time
+#[cw_serde]
+pub struct MintPhase {
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub price: PaymentAsset,
+    pub max_per_wallet: Option<u32>,
+    // only the allowlisted addresses can mint if set
+    pub has_allowlist: bool,
+    // the token uri of a minted token is {base_token_uri}/{token_id}
+    pub base_token_uri: String,
+    // the addresses proving their allocation in the merkle tree can also mint if set
+    #[serde(default)]
+    pub merkle_root: Option<String>,
+}
+
+impl MintPhase {
+    pub fn is_active(&self, block_info: &BlockInfo) -> bool {
+        self.start_time <= block_info.time && block_info.time < self.end_time
+    }
+}
+
+#[cw_serde]
+pub struct CollectionMintersResponse {
+    pub minters: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct MintPhaseResponse {
+    pub phase_id: u32,
+    pub phase: MintPhase,
+}
+
+#[cw_serde]
+pub struct MintPhasesResponse {
+    pub phases: Vec<MintPhaseResponse>,
+}
+
+#[cw_serde]
+pub struct WalletMintsResponse {
+    pub minted: u32,
+    pub allowlisted: bool,
+}
+
+// the token metadata stored on-chain, compatible with cw721-metadata-onchain
+#[cw_serde]
+#[derive(Default)]
+pub struct Metadata {
+    pub image: Option<String>,
+    pub image_data: Option<String>,
+    pub external_url: Option<String>,
+    pub description: Option<String>,
+    pub name: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+    pub background_color: Option<String>,
+    pub animation_url: Option<String>,
+    pub youtube_url: Option<String>,
+}
+
+#[cw_serde]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+// the share of every sale paid to the royalty recipient
+#[cw_serde]
+pub struct Royalty {
+    // in basis points, 100 bps = 1%
+    pub bps: u16,
+    pub recipient: Addr,
+}
+
+impl Royalty {
+    pub fn amount(&self, price: u128) -> u128 {
+        Uint128::from(price)
+            .multiply_ratio(self.bps, BPS_DENOMINATOR)
+            .u128()
+    }
+}
+
+pub const BPS_DENOMINATOR: u16 = 10_000;
+// the seller always receives at least 90% of the price
+pub const MAX_ROYALTY_BPS: u16 = 1_000;
+
+// a collection waiting for the reply of its instantiation
+#[cw_serde]
+pub struct PendingCollection {
+    pub collection: CollectionInfo,
+}
+
+#[cw_serde]
+pub struct CollectionResponse {
+    pub contract_address: Addr,
+    pub collection: CollectionInfo,
+    // the number of tokens minted through this contract
+    pub minted: u64,
+}
+
+#[cw_serde]
+pub struct CollectionsResponse {
+    pub collections: Vec<CollectionResponse>,
+}
+
+// a collection curated by the admins
+#[cw_serde]
+pub struct CollectionRegistration {
+    pub verified: bool,
+    pub display_name: String,
+    // if set, the listings of this collection must be paid with one of these denoms,
+    // a denom is the native denom or the address of the cw20 contract
+    pub payment_denoms: Option<Vec<String>>,
+}
+
+impl Config {
+    pub fn is_owner(&self, address: &Addr) -> bool {
+        self.owner.as_ref() == Some(address)
+    }
+}
+
+// the roles that can be granted to delegate the administration
+// the owner implicitly holds every role
+#[cw_serde]
+pub enum Role {
+    FeeManager,
+    TokenCurator,
+    CollectionCurator,
+    Pauser,
+    Moderator,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::FeeManager => "fee_manager",
+            Role::TokenCurator => "token_curator",
+            Role::CollectionCurator => "collection_curator",
+            Role::Pauser => "pauser",
+            Role::Moderator => "moderator",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RoleHoldersResponse {
+    pub holders: Vec<Addr>,
+}
+
+// the operations that can be paused independently
+#[cw_serde]
+pub enum PausableOperation {
+    Listing,
+    Buying,
+    Minting,
+}
+
+impl PausableOperation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PausableOperation::Listing => "listing",
+            PausableOperation::Buying => "buying",
+            PausableOperation::Minting => "minting",
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseStatus {
+    pub listing: bool,
+    pub buying: bool,
+    pub minting: bool,
+}
+
+impl PauseStatus {
+    pub fn is_paused(&self, operation: &PausableOperation) -> bool {
+        match operation {
+            PausableOperation::Listing => self.listing,
+            PausableOperation::Buying => self.buying,
+            PausableOperation::Minting => self.minting,
+        }
+    }
+
+    pub fn set(&mut self, operation: &PausableOperation, paused: bool) {
+        match operation {
+            PausableOperation::Listing => self.listing = paused,
+            PausableOperation::Buying => self.buying = paused,
+            PausableOperation::Minting => self.minting = paused,
+        }
+    }
 }
 
 #[cw_serde]
@@ -97,18 +486,22 @@ pub enum PaymentAsset {
     },
 }
 
-impl From<Asset> for PaymentAsset {
-    fn from(asset: Asset) -> Self {
+impl TryFrom<Asset> for PaymentAsset {
+    type Error = ContractError;
+
+    fn try_from(asset: Asset) -> Result<Self, Self::Error> {
         match asset {
-            Asset::Native(NativeAsset { denom, amount }) => PaymentAsset::Native { denom, amount },
+            Asset::Native(NativeAsset { denom, amount }) => {
+                Ok(PaymentAsset::Native { denom, amount })
+            }
             Asset::Cw20(Cw20Asset {
                 contract_address,
                 amount,
-            }) => PaymentAsset::Cw20 {
+            }) => Ok(PaymentAsset::Cw20 {
                 contract_address,
                 amount,
-            },
-            _ => panic!("Invalid payment asset"),
+            }),
+            Asset::Nft(_) => Err(ContractError::InvalidPaymentAsset {}),
         }
     }
 }
@@ -119,6 +512,25 @@ pub struct NftAsset {
     pub token_id: Option<String>,
 }
 
+impl PaymentAsset {
+    pub fn amount(&self) -> u128 {
+        match self {
+            PaymentAsset::Native { amount, .. } => *amount,
+            PaymentAsset::Cw20 { amount, .. } => *amount,
+        }
+    }
+
+    // the native denom or the address of the cw20 contract
+    pub fn denom(&self) -> String {
+        match self {
+            PaymentAsset::Native { denom, .. } => denom.clone(),
+            PaymentAsset::Cw20 {
+                contract_address, ..
+            } => contract_address.to_string(),
+        }
+    }
+}
+
 #[cw_serde]
 pub struct Cw20Asset {
     pub contract_address: Addr,
@@ -214,9 +626,37 @@ pub struct Order {
     pub consideration: Vec<ConsiderationItem>,
     pub start_time: Option<Expiration>,
     pub end_time: Option<Expiration>,
+    // the listing is private to the addresses of the merkle tree if set
+    #[serde(default)]
+    pub merkle_root: Option<String>,
+    // the block height at which the order was created
+    #[serde(default)]
+    pub height: u64,
+    // the listing bond paid by the seller
+    #[serde(default)]
+    pub bond: Option<Coin>,
+    // increased every time the listing is updated in place
+    #[serde(default)]
+    pub version: u32,
 }
 
 impl Order {
+    // the denom and the amount of the price of a listing, used to sort the listings by price
+    pub fn price_key(&self) -> (String, u128) {
+        match self
+            .consideration
+            .first()
+            .map(|consideration| &consideration.item)
+        {
+            Some(Asset::Native(NativeAsset { denom, amount })) => (denom.clone(), *amount),
+            Some(Asset::Cw20(Cw20Asset {
+                contract_address,
+                amount,
+            })) => (contract_address.to_string(), *amount),
+            _ => (String::new(), 0),
+        }
+    }
+
     // expired is when a listing has passed the end_time
     pub fn is_expired(&self, block_info: &BlockInfo) -> bool {
         match self.end_time {
@@ -224,9 +664,163 @@ impl Order {
             None => false,
         }
     }
+
+    pub fn status(&self, block_info: &BlockInfo) -> ListingStatus {
+        if self.is_expired(block_info) {
+            ListingStatus::Expired
+        } else if self
+            .start_time
+            .is_some_and(|start_time| !start_time.is_expired(block_info))
+        {
+            ListingStatus::NotStarted
+        } else {
+            ListingStatus::Active
+        }
+    }
+
+    // the cursor to request the listings after this one
+    pub fn cursor(&self) -> ListingCursor {
+        let (denom, price) = self.price_key();
+        ListingCursor {
+            token_id: self.order_id.2.clone(),
+            denom,
+            price,
+            height: self.height,
+        }
+    }
+}
+
+#[cw_serde]
+pub enum ListingStatus {
+    // the listing can be bought
+    Active,
+    // the start_time of the listing is not reached yet
+    NotStarted,
+    // the end_time of the listing has passed
+    Expired,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum ListingsSort {
+    #[default]
+    PriceAsc,
+    PriceDesc,
+    // the most recently listed first
+    Newest,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+// every field which is set must match the listing
+#[cw_serde]
+#[derive(Default)]
+pub struct ListingsFilter {
+    // the native denom or the cw20 contract address of the price
+    pub denom: Option<String>,
+    pub min_price: Option<u128>,
+    pub max_price: Option<u128>,
+    pub seller: Option<String>,
+    pub status: Option<ListingStatus>,
+}
+
+// the position of the last listing of a page, the next page starts after it
+#[cw_serde]
+pub struct ListingCursor {
+    pub token_id: String,
+    pub denom: String,
+    pub price: u128,
+    pub height: u64,
+}
+
+// the trading stats of a collection for one payment denom
+#[cw_serde]
+#[derive(Default)]
+pub struct CollectionStats {
+    pub volume: u128,
+    pub sales_count: u64,
+    pub last_sale_price: Option<u128>,
+    // the stored listings, including the listings not started yet and the expired listings
+    // not swept yet
+    pub stored_listing_count: u64,
+}
+
+#[cw_serde]
+pub struct DenomStatsResponse {
+    // the native denom or the cw20 contract address
+    pub denom: String,
+    pub stats: CollectionStats,
+    // the lowest price of the active listings
+    pub floor_price: Option<u128>,
+}
+
+#[cw_serde]
+pub struct CollectionStatsResponse {
+    pub stats: Vec<DenomStatsResponse>,
+}
+
+// a completed sale of a listed NFT, kept after the listing is removed
+#[cw_serde]
+pub struct Sale {
+    pub sale_id: u64,
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub buyer: Addr,
+    pub price: PaymentAsset,
+    // the part of the price paid to the royalty recipient
+    pub royalty_amount: u128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+// the sales returned by the SalesHistory query
+#[cw_serde]
+pub enum SalesFilter {
+    All {},
+    Collection {
+        contract_address: String,
+    },
+    Token {
+        contract_address: String,
+        token_id: String,
+    },
+    Buyer {
+        address: String,
+    },
+    Seller {
+        address: String,
+    },
+}
+
+#[cw_serde]
+pub struct SalesResponse {
+    pub sales: Vec<Sale>,
+}
+
+#[cw_serde]
+pub struct ListingResponse {
+    pub listing: Order,
+    // whether the collection of the listing is verified in the registry
+    pub verified: bool,
 }
 
 #[cw_serde]
 pub struct ListingsResponse {
-    pub listings: Vec<Order>,
+    pub listings: Vec<ListingResponse>,
+}
+
+// a page of the listings matching a filter, only a bounded number of listings is read per page,
+// so a page can be shorter than the limit, or empty, while next is set
+#[cw_serde]
+pub struct FilteredListingsResponse {
+    pub listings: Vec<ListingResponse>,
+    // the cursor of the next page, None once every listing was read
+    pub next: Option<ListingCursor>,
 }
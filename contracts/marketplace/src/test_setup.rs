@@ -1,6 +1,6 @@
 #[cfg(test)]
 pub mod env {
-    use cosmwasm_std::{Addr, Coin, Empty, Uint128};
+    use cosmwasm_std::{Addr, Coin, DepsMut, Empty, Env, Response, StdResult, Uint128};
     use cw20::Cw20Coin;
     use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
 
@@ -9,10 +9,15 @@ pub mod env {
         query as MarketPlaceQuery, reply as MarketplaceReply,
     };
     use crate::msg::InstantiateMsg;
+    use crate::structs::{CollectionKind, CollectionTemplate, Metadata};
     use cw721_base::entry::{
         execute as cw721Execute, instantiate as cw721Instantiate, query as cw721Query,
     };
     use cw721_base::msg::InstantiateMsg as cw721InstantiateMsg;
+    use cw721_base::Cw721Contract;
+
+    // the cw721 contract storing the metadata on-chain
+    type Cw721MetadataContract<'a> = Cw721Contract<'a, Option<Metadata>, Empty, Empty, Empty>;
 
     // ****************************************
     // You MUST define the constants value here
@@ -73,6 +78,28 @@ pub mod env {
         Box::new(contract)
     }
 
+    fn cw721_metadata_contract_template() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            |deps, env, info, msg| Cw721MetadataContract::default().execute(deps, env, info, msg),
+            |deps, env, info, msg| {
+                Cw721MetadataContract::default().instantiate(deps, env, info, msg)
+            },
+            |deps, env, msg| Cw721MetadataContract::default().query(deps, env, msg),
+        );
+        Box::new(contract)
+    }
+
+    // a new version of the cw721 contract, which created collections can be migrated to
+    pub fn cw721_upgraded_contract_template() -> Box<dyn Contract<Empty>> {
+        fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
+            cw2::set_contract_version(deps.storage, "crates.io:cw721-base", "0.19.0")?;
+            Ok(Response::new())
+        }
+        let contract =
+            ContractWrapper::new(cw721Execute, cw721Instantiate, cw721Query).with_migrate(migrate);
+        Box::new(contract)
+    }
+
     fn nft_marketplace_contract_template() -> Box<dyn Contract<Empty>> {
         let contract =
             ContractWrapper::new(MarketPlaceExecute, MarketPlaceInstantiate, MarketPlaceQuery)
@@ -182,6 +209,9 @@ pub mod env {
             contract_code_id: cw721_contract_code_id,
         });
 
+        // Cw721 contract storing the metadata on-chain, only used by the created collections
+        let cw721_metadata_contract_code_id = app.store_code(cw721_metadata_contract_template());
+
         // NFT Marketplace contract
         // store the code of all contracts to the app and get the code ids
         let marketplace_contract_code_id = app.store_code(nft_marketplace_contract_template());
@@ -189,7 +219,18 @@ pub mod env {
         // create instantiate message for contract
         let msg = InstantiateMsg {
             owner: Addr::unchecked(OWNER),
-            collection_code_id: cw721_contract_code_id,
+            collection_templates: vec![
+                CollectionTemplate {
+                    name: "standard".to_string(),
+                    code_id: cw721_contract_code_id,
+                    kind: CollectionKind::Standard,
+                },
+                CollectionTemplate {
+                    name: "onchain-metadata".to_string(),
+                    code_id: cw721_metadata_contract_code_id,
+                    kind: CollectionKind::OnchainMetadata,
+                },
+            ],
         };
 
         // instantiate contract
@@ -248,4 +289,12 @@ pub mod env {
         // return the app instance, the addresses and code IDs of all contracts
         (app, contract_info_vec)
     }
+
+    // the balance of the address in the native denom
+    pub fn native_balance(app: &App, address: &str) -> Uint128 {
+        app.wrap()
+            .query_balance(address, NATIVE_DENOM)
+            .unwrap()
+            .amount
+    }
 }
@@ -117,7 +117,13 @@ async function main() {
     // prepare instantiate message for cw20 token
     const marketplaceInstantiateMsg = {
         owner: deployerAccount.address,
-        collection_code_id: storeCodeResponseCw721.codeId
+        collection_templates: [
+            {
+                name: "standard",
+                code_id: storeCodeResponseCw721.codeId,
+                kind: "standard"
+            }
+        ]
     }
 
     let marketplaceInstantiateResponse = await instantiate(storeCodeResponseMarketplace.codeId, marketplaceInstantiateMsg);